@@ -0,0 +1,73 @@
+//Scans command line arguments for flags, so each day does not need to hand-parse
+//std::env::args. These take any iterator of arguments, such as std::env::args()
+//or a slice of Strings.
+
+//True when the named flag appears anywhere in the arguments.
+pub fn has_flag<I, S>(args: I, name: &str) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().any(|arg| arg.as_ref() == name)
+}
+
+//Returns the argument following the first occurrence of the named flag.
+pub fn flag_value<I, S>(args: I, name: &str) -> Option<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .skip_while(|arg| arg.as_ref() != name)
+        .nth(1)
+        .map(|arg| arg.as_ref().to_string())
+}
+
+//Returns the arguments following the named flag, up to the next flag.
+pub fn flag_values<I, S>(args: I, name: &str) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .skip_while(|arg| arg.as_ref() != name)
+        .skip(1)
+        .take_while(|arg| !arg.as_ref().starts_with("--"))
+        .map(|arg| arg.as_ref().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARGS: [&str; 7] = ["day", "--render", "--on", "@", "--pairs", "A", "B"];
+
+    #[test]
+    fn test_has_flag() {
+        assert!(has_flag(ARGS.iter(), "--render"));
+        assert!(has_flag(ARGS.iter(), "--pairs"));
+        assert!(!has_flag(ARGS.iter(), "--stats"));
+    }
+
+    #[test]
+    fn test_flag_value() {
+        assert_eq!(Some("@".to_string()), flag_value(ARGS.iter(), "--on"));
+        assert_eq!(
+            Some("--on".to_string()),
+            flag_value(ARGS.iter(), "--render")
+        );
+        assert_eq!(None, flag_value(ARGS.iter(), "--stats"));
+        assert_eq!(None, flag_value(["day", "--on"].iter(), "--on"));
+        let owned: Vec<String> = ARGS.iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(Some("@".to_string()), flag_value(&owned, "--on"));
+    }
+
+    #[test]
+    fn test_flag_values() {
+        assert_eq!(vec!["A", "B"], flag_values(ARGS.iter(), "--pairs"));
+        assert_eq!(vec!["@"], flag_values(ARGS.iter(), "--on"));
+        assert!(flag_values(ARGS.iter(), "--render").is_empty());
+        assert!(flag_values(ARGS.iter(), "--stats").is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+
+//Errors that come up in most days, so each day does not need to define its own.
+#[derive(Debug)]
+pub enum AocError {
+    Parse(String),
+    Io(std::io::Error),
+    AnswerNotFound,
+}
+
+impl Error for AocError {}
+
+impl From<std::io::Error> for AocError {
+    fn from(err: std::io::Error) -> AocError {
+        AocError::Io(err)
+    }
+}
+
+impl From<ParseIntError> for AocError {
+    fn from(err: ParseIntError) -> AocError {
+        AocError::Parse(err.to_string())
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AocError::Parse(msg) => write!(f, "parse error: {}", msg),
+            AocError::Io(io_err) => write!(f, "io error: {}", io_err),
+            AocError::AnswerNotFound => write!(f, "answer not found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "parse error: bad line",
+            AocError::Parse("bad line".to_string()).to_string()
+        );
+        assert_eq!(
+            "io error: missing",
+            AocError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "missing")).to_string()
+        );
+        assert_eq!("answer not found", AocError::AnswerNotFound.to_string());
+    }
+
+    #[test]
+    fn test_from() {
+        let err: AocError = "turtle".parse::<i32>().unwrap_err().into();
+        match err {
+            AocError::Parse(_) => {}
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+extern crate aoc_error;
+
+use aoc_error::AocError;
 use std::error::Error;
 
 fn calculate_fuel_required(mass: u32) -> u32 {
@@ -8,6 +11,8 @@ fn calculate_fuel_required(mass: u32) -> u32 {
     }
 }
 
+//Each step divides the mass by three, so the recursion is only about log3(mass)
+//deep; a u32 mass never goes past 21 levels.
 fn calculate_fuel_required_including_fuel(mass: u32) -> u32 {
     if mass == 0 {
         0
@@ -17,18 +22,55 @@ fn calculate_fuel_required_including_fuel(mass: u32) -> u32 {
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+//Same result as calculate_fuel_required_including_fuel, computed with a loop
+//instead of recursion. The tests check that the two agree.
+#[cfg(test)]
+fn calculate_fuel_required_including_fuel_iterative(mass: u32) -> u32 {
+    let mut total = 0;
+    let mut fuel = calculate_fuel_required(mass);
+    while fuel != 0 {
+        total += fuel;
+        fuel = calculate_fuel_required(fuel);
+    }
+    total
+}
+
+//Returns the naive total and the total that includes the fuel's own mass.
+fn totals(masses: impl Iterator<Item = u32>) -> (u32, u32) {
     let mut naive_total = 0;
     let mut total = 0;
-    for line in std::fs::read_to_string("input.txt")?.lines() {
-        let mass = u32::from_str_radix(line, 10)?;
+    for mass in masses {
         naive_total += calculate_fuel_required(mass);
         total += calculate_fuel_required_including_fuel(mass);
     }
+    (naive_total, total)
+}
+
+fn parse_masses(text: &str) -> Result<Vec<u32>, AocError> {
+    let mut masses = Vec::new();
+    for (ndx, line) in text.lines().enumerate() {
+        match line.parse::<u32>() {
+            Ok(mass) => masses.push(mass),
+            Err(_) => {
+                //Line numbers start at 1.
+                return Err(AocError::Parse(format!(
+                    "invalid mass on line {}: {:?}",
+                    ndx + 1,
+                    line
+                )));
+            }
+        }
+    }
+    Ok(masses)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let masses = parse_masses(&std::fs::read_to_string("input.txt")?)?;
+    let (naive_total, total) = totals(masses.into_iter());
 
     println!("Total fuel needed: {}", naive_total);
     println!("Total fuel needed (including fuel): {}", total);
-    return Ok(());
+    Ok(())
 }
 
 #[cfg(test)]
@@ -46,4 +88,37 @@ mod tests {
         assert_eq!(966, calculate_fuel_required_including_fuel(1969));
         assert_eq!(50346, calculate_fuel_required_including_fuel(100756));
     }
+
+    #[test]
+    fn test_totals() {
+        let masses = vec![12, 14, 1969, 100756];
+        assert_eq!(
+            (2 + 2 + 654 + 33583, 2 + 2 + 966 + 50346),
+            totals(masses.into_iter())
+        );
+    }
+
+    #[test]
+    fn test_parse_masses() {
+        assert_eq!(vec![12, 14], parse_masses("12\n14\n").unwrap());
+        let err = parse_masses("12\n-5\n14").unwrap_err();
+        assert_eq!(
+            "parse error: invalid mass on line 2: \"-5\"",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_iterative_matches_recursive() {
+        for mass in 0..=1_000_000 {
+            assert_eq!(
+                calculate_fuel_required_including_fuel(mass),
+                calculate_fuel_required_including_fuel_iterative(mass)
+            );
+        }
+        assert_eq!(
+            calculate_fuel_required_including_fuel(u32::MAX),
+            calculate_fuel_required_including_fuel_iterative(u32::MAX)
+        );
+    }
 }
@@ -1,46 +1,34 @@
 use std::error::Error;
-use std::fmt;
 
+extern crate aoc_error;
 extern crate intcode;
 
-#[derive(Debug)]
-enum MyError {
-    AnswerNotFound
-}
-
-impl Error for MyError {}
+use aoc_error::AocError;
 
-impl fmt::Display for MyError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            MyError::AnswerNotFound => write!(f, "answer not found"),
-        }
-    }
+//Runs the program with the noun and verb stored at addresses 1 and 2, returning address 0.
+fn run_with(program: &[i64], noun: i64, verb: i64) -> Result<i64, intcode::IntcodeError> {
+    let mut mem = program.to_owned();
+    mem[1] = noun;
+    mem[2] = verb;
+    intcode::execute_no_io(&mut mem)?;
+    Ok(mem[0])
 }
 
 fn part1(source: &[i64]) -> Result<(), intcode::IntcodeError> {
-    let mut mem = source.to_owned();
-    mem[1] = 12;
-    mem[2] = 2;
-    intcode::execute_no_io(&mut mem)?;
-    println!("part 1: {}", mem[0]);
+    println!("part 1: {}", run_with(source, 12, 2)?);
     Ok(())
 }
 
 fn part2(source: &[i64]) -> Result<(), Box<dyn Error>> {
     for noun in 0..100 {
         for verb in 0..100 {
-            let mut mem = source.to_owned();
-            mem[1] = noun;
-            mem[2] = verb;
-            intcode::execute_no_io(&mut mem)?;
-            if mem[0] == 19_690_720 {
+            if run_with(source, noun, verb)? == 19_690_720 {
                 println!("part2: {}", 100 * noun + verb);
                 return Ok(());
             }
         }
     }
-    Err(Box::new(MyError::AnswerNotFound))
+    Err(Box::new(AocError::AnswerNotFound))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -49,3 +37,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     part2(&mem)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with() {
+        let program =
+            intcode::parse_program("1,9,10,3,2,3,11,0,99,30,40,50").expect("parse program");
+        assert_eq!(3500, run_with(&program, 9, 10).expect("execute failed"));
+        //(40 + 40) * 50
+        assert_eq!(4000, run_with(&program, 10, 10).expect("execute failed"));
+    }
+}
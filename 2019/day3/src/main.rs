@@ -4,6 +4,8 @@ use std::error::Error;
 use std::fmt;
 use std::str::Lines;
 
+extern crate aoc_args;
+
 #[derive(Debug)]
 enum MyError {
     LineParseError,
@@ -43,7 +45,7 @@ fn get_move_for_spec(movement: &str) -> Result<Movement, MyError> {
         return Err(MyError::LineParseError);
     };
     let amount = if let Some((split_index, _)) = char_iter.next() {
-        if let Ok(amount) = u32::from_str_radix(movement.split_at(split_index).1, 10) {
+        if let Ok(amount) = movement.split_at(split_index).1.parse::<u32>() {
             amount
         } else {
             return Err(MyError::LineParseError);
@@ -90,38 +92,59 @@ fn parse_next_wire_location(lines: &mut Lines) -> Result<HashMap<(i32, i32), u32
     }
 }
 
-fn find_closest_intersection_by_manhattan(
+//Finds the closest intersection by both Manhattan distance and combined wire
+//length, visiting each shared location once.
+fn closest_intersections(
     map1: &HashMap<(i32, i32), u32>,
     map2: &HashMap<(i32, i32), u32>,
-) -> Option<i32> {
-    let mut ret = None;
-    for loc in map1.keys() {
-        if map2.get(loc).is_some() {
+) -> (Option<i32>, Option<u32>) {
+    let mut manhattan: Option<i32> = None;
+    let mut wire_length: Option<u32> = None;
+    for (loc, length1) in map1 {
+        if let Some(length2) = map2.get(loc) {
             let dist = loc.0.abs() + loc.1.abs();
-            ret = if let Some(best) = ret {
-                Some(min(dist, best))
-            } else {
-                Some(dist)
-            };
+            manhattan = Some(manhattan.map_or(dist, |best| min(dist, best)));
+            let dist = length1 + length2;
+            wire_length = Some(wire_length.map_or(dist, |best| min(dist, best)));
         }
     }
-    ret
+    (manhattan, wire_length)
 }
 
-fn find_closest_intersection_by_wire_length(
-    map1: &HashMap<(i32, i32), u32>,
-    map2: &HashMap<(i32, i32), u32>,
-) -> Option<u32> {
-    let mut ret: Option<u32> = None;
-    for (loc, length1) in map1 {
-        if let Some(length2) = map2.get(loc) {
-            let dist = length1 + length2;
-            ret = if let Some(best) = ret {
-                Some(min(dist, best))
+//Draws each wire using its 1-based index, with 'X' where wires cross and 'o'
+//at the origin. The y axis points up, matching the U and D movements.
+fn render_wires(wires: &[HashMap<(i32, i32), u32>]) -> String {
+    let all_points = || {
+        wires
+            .iter()
+            .flat_map(|wire| wire.keys())
+            .chain([(0, 0)].iter())
+    };
+    let min_x = all_points().map(|loc| loc.0).min().unwrap();
+    let max_x = all_points().map(|loc| loc.0).max().unwrap();
+    let min_y = all_points().map(|loc| loc.1).min().unwrap();
+    let max_y = all_points().map(|loc| loc.1).max().unwrap();
+
+    let mut ret = String::new();
+    for y in (min_y..=max_y).rev() {
+        for x in min_x..=max_x {
+            let loc = (x, y);
+            let mut on_wires = wires
+                .iter()
+                .enumerate()
+                .filter(|(_, wire)| wire.contains_key(&loc))
+                .map(|(ndx, _)| ndx);
+            ret.push(if loc == (0, 0) {
+                'o'
             } else {
-                Some(dist)
-            };
+                match (on_wires.next(), on_wires.next()) {
+                    (None, _) => '.',
+                    (Some(ndx), None) => std::char::from_digit((ndx + 1) as u32, 10).unwrap_or('#'),
+                    (Some(_), Some(_)) => 'X',
+                }
+            });
         }
+        ret.push('\n');
     }
     ret
 }
@@ -131,16 +154,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut lines = file_contents.lines();
     let wire1 = parse_next_wire_location(&mut lines)?;
     let wire2 = parse_next_wire_location(&mut lines)?;
-    if let Some(dist) = find_closest_intersection_by_manhattan(&wire1, &wire2) {
+    let (manhattan, wire_length) = closest_intersections(&wire1, &wire2);
+    if let Some(dist) = manhattan {
         println!("found Manhattan dist: {}", dist);
     } else {
         println!("failed to find Manhattan dist");
     }
-    if let Some(dist) = find_closest_intersection_by_wire_length(&wire1, &wire2) {
+    if let Some(dist) = wire_length {
         println!("found wire dist: {}", dist);
     } else {
         println!("failed to find wire dist");
     }
+    if aoc_args::has_flag(std::env::args(), "--render") {
+        print!("{}", render_wires(&[wire1, wire2]));
+    }
     Ok(())
 }
 
@@ -189,15 +216,7 @@ mod test {
     fn test_parse_wire_location1() {
         let map1 = parse_wire_location("R8,U5,L5,D3").expect("failed to add");
         let map2 = parse_wire_location("U7,R6,D4,L4").expect("failed to add");
-        assert_eq!(
-            6,
-            find_closest_intersection_by_manhattan(&map1, &map2).expect("failed to find shortest")
-        );
-        assert_eq!(
-            30,
-            find_closest_intersection_by_wire_length(&map1, &map2)
-                .expect("failed to find shortest")
-        );
+        assert_eq!((Some(6), Some(30)), closest_intersections(&map1, &map2));
     }
 
     #[test]
@@ -205,15 +224,7 @@ mod test {
         let map1 =
             parse_wire_location("R75,D30,R83,U83,L12,D49,R71,U7,L72").expect("failed to add");
         let map2 = parse_wire_location("U62,R66,U55,R34,D71,R55,D58,R83").expect("failed to add");
-        assert_eq!(
-            159,
-            find_closest_intersection_by_manhattan(&map1, &map2).expect("failed to find shortest")
-        );
-        assert_eq!(
-            610,
-            find_closest_intersection_by_wire_length(&map1, &map2)
-                .expect("failed to find shortest")
-        );
+        assert_eq!((Some(159), Some(610)), closest_intersections(&map1, &map2));
     }
 
     #[test]
@@ -222,14 +233,32 @@ mod test {
             .expect("failed to add");
         let map2 =
             parse_wire_location("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").expect("failed to add");
+        assert_eq!((Some(135), Some(410)), closest_intersections(&map1, &map2));
+    }
+
+    #[test]
+    fn test_closest_intersections_no_crossing() {
+        let map1 = parse_wire_location("R8").expect("failed to add");
+        let map2 = parse_wire_location("U7").expect("failed to add");
+        assert_eq!((None, None), closest_intersections(&map1, &map2));
+    }
+
+    #[test]
+    fn test_render_wires() {
+        let map1 = parse_wire_location("R8,U5,L5,D3").expect("failed to add");
+        let map2 = parse_wire_location("U7,R6,D4,L4").expect("failed to add");
         assert_eq!(
-            135,
-            find_closest_intersection_by_manhattan(&map1, &map2).expect("failed to find shortest")
-        );
-        assert_eq!(
-            410,
-            find_closest_intersection_by_wire_length(&map1, &map2)
-                .expect("failed to find shortest")
+            "\
+2222222..
+2.....2..
+2..111X11
+2..1..2.1
+2.2X222.1
+2..1....1
+2.......1
+o11111111
+",
+            render_wires(&[map1, map2])
         );
     }
 }
@@ -1,4 +1,8 @@
-#[derive(Debug, PartialEq)]
+use std::collections::HashMap;
+
+extern crate aoc_args;
+
+#[derive(Debug, PartialEq, Eq, Hash)]
 enum PasswordValidity {
     Valid,
     WrongLength,
@@ -40,91 +44,111 @@ fn is_valid_password(password: u32) -> PasswordValidity {
     }
 }
 
-enum DigitState {
-    None,
-    One(u32),
-    Two(u32),
-    More(u32),
-}
-
-fn is_valid_password2(password: u32) -> PasswordValidity {
+//True when the password has a run of exactly two identical adjacent digits.
+//Longer runs, like the 444 in 123444, do not count.
+fn has_exact_pair(password: u32) -> bool {
     let mut pass = password;
-    let mut num_digits = 0;
-    let mut repeated_digits = false;
-    let mut prev: DigitState = DigitState::None;
+    let mut prev: Option<u32> = None;
+    let mut run_length = 0;
     while pass != 0 {
-        num_digits += 1;
         let digit = pass % 10;
-
-        {
-            let p = match prev {
-                DigitState::One(p) => p,
-                DigitState::Two(p) => p,
-                DigitState::More(p) => p,
-                DigitState::None => 99, // larger than any possible digit
-            };
-            if p < digit {
-                return PasswordValidity::NotMonotonicallyIncrasing;
+        if prev == Some(digit) {
+            run_length += 1;
+        } else {
+            if run_length == 2 {
+                return true;
             }
+            run_length = 1;
         }
+        pass /= 10;
+        prev = Some(digit);
+    }
+    run_length == 2
+}
 
+fn is_valid_password2(password: u32) -> PasswordValidity {
+    match is_valid_password(password) {
+        PasswordValidity::Valid if !has_exact_pair(password) => PasswordValidity::NoRepeatedDigits,
+        other => other,
+    }
+}
+
+//Splits a six digit password into its digits, most significant first.
+fn to_digits(password: u32) -> Option<[u8; 6]> {
+    if !(100000..=999999).contains(&password) {
+        return None;
+    }
+    let mut digits = [0u8; 6];
+    let mut pass = password;
+    for digit in digits.iter_mut().rev() {
+        *digit = (pass % 10) as u8;
         pass /= 10;
-        prev = match prev {
-            DigitState::None => DigitState::One(digit),
-            DigitState::One(p) => {
-                if p == digit {
-                    DigitState::Two(digit)
-                } else {
-                    DigitState::One(digit)
-                }
-            }
-            DigitState::Two(p) => {
-                if p == digit {
-                    DigitState::More(digit)
-                } else {
-                    repeated_digits = true;
-                    DigitState::One(digit)
-                }
-            }
-            DigitState::More(p) => {
-                if p == digit {
-                    DigitState::More(digit)
-                } else {
-                    DigitState::One(digit)
-                }
-            }
-        }
     }
+    Some(digits)
+}
+
+//Same results as is_valid_password and is_valid_password2, but checks an array
+//of digits instead of dividing the number again for every rule.
+fn is_valid_digits(password: u32, part2: bool) -> PasswordValidity {
+    let digits = match to_digits(password) {
+        Some(digits) => digits,
+        //Other lengths are rare, so let the original checks decide how to report them.
+        None if part2 => return is_valid_password2(password),
+        None => return is_valid_password(password),
+    };
 
-    if let DigitState::Two(_) = prev {
-        repeated_digits = true;
+    if digits.windows(2).any(|w| w[0] > w[1]) {
+        return PasswordValidity::NotMonotonicallyIncrasing;
     }
 
-    if num_digits != 6 {
-        PasswordValidity::WrongLength
-    } else if !repeated_digits {
-        PasswordValidity::NoRepeatedDigits
+    let has_pair = if part2 {
+        //Since the digits never decrease, equal digits are always adjacent.
+        digits
+            .iter()
+            .any(|d| digits.iter().filter(|other| *other == d).count() == 2)
     } else {
+        digits.windows(2).any(|w| w[0] == w[1])
+    };
+    if has_pair {
         PasswordValidity::Valid
+    } else {
+        PasswordValidity::NoRepeatedDigits
     }
 }
 
-fn main() {
-    let mut valid_passwords = 0;
-    for i in 171309..643603 {
-        if is_valid_password(i) == PasswordValidity::Valid {
-            valid_passwords += 1;
-        }
+//Counts how many of the candidates in start..end fall into each category.
+fn tally_reasons(
+    start: u32,
+    end: u32,
+    check: fn(u32) -> PasswordValidity,
+) -> HashMap<PasswordValidity, usize> {
+    let mut ret = HashMap::new();
+    for i in start..end {
+        *ret.entry(check(i)).or_insert(0) += 1;
     }
-    println!("valid passwords part1: {}", valid_passwords);
+    ret
+}
 
-    let mut valid_passwords = 0;
-    for i in 171309..643603 {
-        if is_valid_password2(i) == PasswordValidity::Valid {
-            valid_passwords += 1;
-        }
+//Yields each password in start..end that passes the part 1 or part 2 rules.
+fn valid_passwords(start: u32, end: u32, part2: bool) -> impl Iterator<Item = u32> {
+    (start..end).filter(move |&p| is_valid_digits(p, part2) == PasswordValidity::Valid)
+}
+
+fn main() {
+    let (start, end) = (171309, 643603);
+    println!(
+        "valid passwords part1: {}",
+        valid_passwords(start, end, false).count()
+    );
+    println!(
+        "valid passwords part2: {}",
+        valid_passwords(start, end, true).count()
+    );
+
+    if aoc_args::has_flag(std::env::args(), "--reasons") {
+        println!("part1: {:?}", tally_reasons(start, end, is_valid_password));
+        println!("part2: {:?}", tally_reasons(start, end, is_valid_password2));
     }
-    println!("valid passwords part2: {}", valid_passwords);
 }
 
 #[cfg(test)]
@@ -153,4 +177,52 @@ mod test {
         );
         assert_eq!(PasswordValidity::Valid, is_valid_password2(111122));
     }
+
+    #[test]
+    fn test_tally_reasons() {
+        let tally = tally_reasons(100000, 200000, is_valid_password);
+        assert_eq!(100000, tally.values().sum::<usize>());
+        let tally = tally_reasons(99990, 100010, is_valid_password2);
+        assert_eq!(20, tally.values().sum::<usize>());
+        assert_eq!(1, tally[&PasswordValidity::WrongLength]);
+    }
+
+    #[test]
+    fn test_has_exact_pair() {
+        assert!(has_exact_pair(112233));
+        assert!(!has_exact_pair(123444));
+        assert!(has_exact_pair(111122));
+        assert!(has_exact_pair(112222));
+        assert!(!has_exact_pair(111111));
+        assert!(!has_exact_pair(123456));
+    }
+
+    #[test]
+    fn test_valid_passwords() {
+        let first = (123000..124000)
+            .find(|&p| is_valid_password2(p) == PasswordValidity::Valid)
+            .unwrap();
+        assert_eq!(Some(first), valid_passwords(123000, 124000, true).next());
+        assert_eq!(Some(123444), valid_passwords(123444, 124000, false).next());
+        assert_eq!(
+            tally_reasons(123000, 124000, is_valid_password)[&PasswordValidity::Valid],
+            valid_passwords(123000, 124000, false).count()
+        );
+    }
+
+    #[test]
+    fn test_is_valid_digits() {
+        assert_eq!(Some([1, 2, 3, 4, 4, 4]), to_digits(123444));
+        assert_eq!(None, to_digits(99999));
+        for password in (99000..200000).chain(990000..1001000) {
+            assert_eq!(
+                is_valid_password(password),
+                is_valid_digits(password, false)
+            );
+            assert_eq!(
+                is_valid_password2(password),
+                is_valid_digits(password, true)
+            );
+        }
+    }
 }
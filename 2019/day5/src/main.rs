@@ -1,13 +1,60 @@
-use std::error::Error;
-
 extern crate intcode;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut mem = intcode::parse_program(&std::fs::read_to_string("input.txt")?)?;
-    intcode::execute(
-        &mut mem,
-        &mut std::io::stdin().lock(),
-        &mut std::io::stdout().lock(),
-    )?;
-    Ok(())
+fn main() -> Result<(), intcode::IntcodeError> {
+    intcode::run_file("input.txt", std::env::args().nth(1).as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    //The answers for this crate's input.txt; see test_all.sh.
+    const PART1_ANSWER: &str = "0\n0\n0\n0\n0\n0\n0\n0\n0\n8332629\n";
+    const PART2_ANSWER: &str = "8805067\n";
+
+    fn run(program: &str, input: &str) -> String {
+        let mut output = Vec::new();
+        intcode::run_with_inputs(program, &mut std::io::Cursor::new(input), &mut output)
+            .expect("execute failed");
+        String::from_utf8(output).unwrap()
+    }
+
+    //Example programs from the puzzle description.
+    #[test]
+    fn test_diagnostic_examples() {
+        assert_eq!("42\n", run("3,0,4,0,99", "42\n"));
+        assert_eq!("", run("1002,4,3,4,33", ""));
+        assert_eq!("", run("1101,100,-1,4,0", ""));
+
+        //Equal to 8, position and immediate mode.
+        assert_eq!("1\n", run("3,9,8,9,10,9,4,9,99,-1,8", "8\n"));
+        assert_eq!("0\n", run("3,9,8,9,10,9,4,9,99,-1,8", "7\n"));
+        assert_eq!("1\n", run("3,3,1108,-1,8,3,4,3,99", "8\n"));
+        //Less than 8, position and immediate mode.
+        assert_eq!("1\n", run("3,9,7,9,10,9,4,9,99,-1,8", "5\n"));
+        assert_eq!("0\n", run("3,3,1107,-1,8,3,4,3,99", "9\n"));
+
+        //Jumps output 0 if the input was zero, 1 otherwise.
+        assert_eq!(
+            "0\n",
+            run("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", "0\n")
+        );
+        assert_eq!("1\n", run("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", "3\n"));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_real_input_part1() {
+        assert_eq!(
+            PART1_ANSWER,
+            intcode::run_file_to_string("input.txt", "1\n").unwrap()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_real_input_part2() {
+        assert_eq!(
+            PART2_ANSWER,
+            intcode::run_file_to_string("input.txt", "5\n").unwrap()
+        );
+    }
 }
@@ -3,25 +3,49 @@ use std::error::Error;
 use std::fmt;
 use std::str::Lines;
 
+extern crate aoc_args;
+
 #[derive(Debug)]
 enum MyError {
-    ParseError,
+    //Line numbers start at 1.
+    ParseError { line: usize, content: String },
     DuplicateEntry,
     MissingLink,
     NodeNotFound,
     Loop,
+    //Transfers start from the object being orbited, and COM orbits nothing.
+    NoParent,
+    //--distances was given a name without a partner.
+    UnpairedName(String),
 }
 impl Error for MyError {}
 impl fmt::Display for MyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            MyError::ParseError { line, content } => {
+                write!(f, "failed to parse line {}: {:?}", line, content)
+            }
+            MyError::UnpairedName(name) => {
+                write!(f, "--distances name {:?} has no partner", name)
+            }
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
 struct Object {
+    name: String,
     parent: Option<usize>,
 }
 
+#[derive(Debug, PartialEq)]
+struct OrbitStats {
+    object_count: usize,
+    //None when the map is not valid, since depths are only defined for a valid map.
+    max_depth: Option<usize>,
+    valid: bool,
+}
+
 struct OrbitMap {
     objects: Vec<Object>,
     object_names: HashMap<String, usize>,
@@ -30,7 +54,10 @@ struct OrbitMap {
 impl OrbitMap {
     fn new() -> OrbitMap {
         let mut ret = OrbitMap {
-            objects: vec![Object { parent: Some(0) }],
+            objects: vec![Object {
+                name: "COM".to_string(),
+                parent: Some(0),
+            }],
             object_names: HashMap::new(),
         };
         ret.object_names.insert("COM".to_string(), 0);
@@ -41,7 +68,10 @@ impl OrbitMap {
         match self.object_names.get(name) {
             Some(ndx) => *ndx,
             None => {
-                self.objects.push(Object { parent: None });
+                self.objects.push(Object {
+                    name: name.to_string(),
+                    parent: None,
+                });
                 let ndx = self.objects.len() - 1;
                 self.object_names.insert(name.to_string(), ndx);
                 ndx
@@ -63,10 +93,13 @@ impl OrbitMap {
     }
 
     fn add_lines(&mut self, lines: &mut Lines) -> Result<(), MyError> {
-        for line in lines {
+        for (ndx, line) in lines.enumerate() {
             let parts: Vec<&str> = line.split(')').collect();
             if parts.len() != 2 {
-                return Err(MyError::ParseError);
+                return Err(MyError::ParseError {
+                    line: ndx + 1,
+                    content: line.to_string(),
+                });
             }
             self.add_orbit(parts[0], parts[1])?;
         }
@@ -75,35 +108,83 @@ impl OrbitMap {
 
     fn validate(&self) -> Result<(), MyError> {
         for obj in &self.objects {
-            if obj.parent == None {
+            if obj.parent.is_none() {
                 return Err(MyError::MissingLink);
             }
         }
         Ok(())
     }
 
-    fn total_number_of_orbits(&self) -> Result<usize, MyError> {
-        fn count_one(map: &OrbitMap, obj: &Object) -> usize {
-            let mut obj = obj;
-            let mut ret = 1;
-            while obj.parent.unwrap() != 0 {
-                obj = &map.objects[obj.parent.unwrap()];
-                ret += 1;
+    //Returns, for each object, the number of objects it orbits directly or indirectly.
+    //This walks the parent links iteratively and memoizes, so long chains neither
+    //recurse nor get rewalked for every object.
+    fn depths(&self) -> Result<Vec<usize>, MyError> {
+        self.validate()?;
+
+        let mut depths: Vec<Option<usize>> = vec![None; self.objects.len()];
+        depths[0] = Some(0);
+        let mut stack = Vec::new();
+        for start in 0..self.objects.len() {
+            let mut ndx = start;
+            while depths[ndx].is_none() {
+                if stack.len() > self.objects.len() {
+                    return Err(MyError::Loop);
+                }
+                stack.push(ndx);
+                ndx = self.objects[ndx].parent.unwrap();
+            }
+            let mut depth = depths[ndx].unwrap();
+            while let Some(ndx) = stack.pop() {
+                depth += 1;
+                depths[ndx] = Some(depth);
             }
-            ret
         }
+        Ok(depths.into_iter().map(|d| d.unwrap()).collect())
+    }
+
+    fn stats(&self) -> OrbitStats {
+        let max_depth = self
+            .depths()
+            .ok()
+            .map(|depths| depths.into_iter().max().unwrap_or(0));
+        OrbitStats {
+            object_count: self.objects.len(),
+            max_depth,
+            valid: max_depth.is_some(),
+        }
+    }
+
+    fn total_number_of_orbits(&self) -> Result<usize, MyError> {
+        Ok(self.depths()?.iter().sum())
+    }
 
+    //Returns the names of the objects that the named object orbits, directly or
+    //indirectly, starting with the object it directly orbits and ending with COM.
+    fn ancestors(&self, name: &str) -> Result<Vec<String>, MyError> {
         self.validate()?;
 
-        let mut ret = 0;
-        for obj in self.objects.iter().skip(1) {
-            //TODO: memoize
-            ret += count_one(self, &obj);
+        let mut ndx = match self.object_names.get(name) {
+            Some(ndx) => *ndx,
+            None => return Err(MyError::NodeNotFound),
+        };
+        let mut ret = Vec::new();
+        while ndx != 0 {
+            ndx = self.objects[ndx].parent.unwrap();
+            ret.push(self.objects[ndx].name.clone());
+            if ret.len() > self.objects.len() {
+                return Err(MyError::Loop);
+            }
         }
         Ok(ret)
     }
 
-    fn find_distance_between(&self, a_name: &str, b_name: &str) -> Result<usize, MyError> {
+    //Returns the number of edges from each node up to their closest common parent,
+    //and the index of that parent.
+    fn find_common_parent(
+        &self,
+        a_name: &str,
+        b_name: &str,
+    ) -> Result<(usize, usize, usize), MyError> {
         self.validate()?;
 
         let a_ndx = match self.object_names.get(a_name) {
@@ -144,13 +225,109 @@ impl OrbitMap {
             }
         }
 
-        //The above code calculates the number of edges from each node to the common parent node.
+        Ok((a_nodes[&common_parent], b_distance, common_parent))
+    }
+
+    fn find_distance_between(&self, a_name: &str, b_name: &str) -> Result<usize, MyError> {
+        let (up, down, _) = self.find_transfer_legs(a_name, b_name)?;
+        Ok(up + down)
+    }
+
+    //Splits the transfer between the objects a and b orbit into the number of transfers
+    //up from a to the object where the paths meet, and the number down from there to b.
+    fn find_transfer_legs(
+        &self,
+        a_name: &str,
+        b_name: &str,
+    ) -> Result<(usize, usize, String), MyError> {
+        let (a_distance, b_distance, common_parent) = self.find_common_parent(a_name, b_name)?;
+        //find_common_parent calculates the number of edges from each node to the common parent node.
         //Since we want to not count moving from ourselves to the orbited planet, substrate one
-        //for each starting node.
-        Ok(b_distance + a_nodes[&common_parent] - 2)
+        //for each starting node. When one node is the common parent, the paths instead meet
+        //at the object it orbits, which is one edge further from both nodes.
+        if a_distance == 0 || b_distance == 0 {
+            let meeting = match self.objects[common_parent].parent {
+                Some(parent) if common_parent != 0 => parent,
+                _ => return Err(MyError::NoParent),
+            };
+            Ok((a_distance, b_distance, self.objects[meeting].name.clone()))
+        } else {
+            Ok((
+                a_distance - 1,
+                b_distance - 1,
+                self.objects[common_parent].name.clone(),
+            ))
+        }
+    }
+}
+
+//Precomputes the depth of every object so that any number of distance queries
+//can be answered by walking up from both objects, without building a map of
+//ancestors for each query.
+struct DistanceIndex<'a> {
+    map: &'a OrbitMap,
+    depths: Vec<usize>,
+}
+
+impl DistanceIndex<'_> {
+    fn new(map: &OrbitMap) -> Result<DistanceIndex<'_>, MyError> {
+        Ok(DistanceIndex {
+            map,
+            depths: map.depths()?,
+        })
+    }
+
+    fn lookup(&self, name: &str) -> Result<usize, MyError> {
+        match self.map.object_names.get(name) {
+            Some(ndx) => Ok(*ndx),
+            None => Err(MyError::NodeNotFound),
+        }
+    }
+
+    //Returns the number of transfers between the objects a and b orbit. This
+    //also works when one of them orbits the other, directly or indirectly.
+    fn find_distance_between(&self, a_name: &str, b_name: &str) -> Result<usize, MyError> {
+        let mut a_ndx = self.lookup(a_name)?;
+        let mut b_ndx = self.lookup(b_name)?;
+        if a_ndx == 0 || b_ndx == 0 {
+            return Err(MyError::NoParent);
+        }
+        let a_depth = self.depths[a_ndx];
+        let b_depth = self.depths[b_ndx];
+
+        while self.depths[a_ndx] > self.depths[b_ndx] {
+            a_ndx = self.map.objects[a_ndx].parent.unwrap();
+        }
+        while self.depths[b_ndx] > self.depths[a_ndx] {
+            b_ndx = self.map.objects[b_ndx].parent.unwrap();
+        }
+        while a_ndx != b_ndx {
+            a_ndx = self.map.objects[a_ndx].parent.unwrap();
+            b_ndx = self.map.objects[b_ndx].parent.unwrap();
+        }
+
+        let common_depth = self.depths[a_ndx];
+        if common_depth == a_depth || common_depth == b_depth {
+            //One object is the common ancestor, so the path between the objects
+            //they orbit is the same length as the path between them.
+            Ok(a_depth.max(b_depth) - common_depth)
+        } else {
+            Ok((a_depth - common_depth) + (b_depth - common_depth) - 2)
+        }
     }
 }
 
+//Groups the names given to --distances into pairs.
+fn distance_pairs(names: &[String]) -> Result<Vec<(String, String)>, MyError> {
+    if !names.len().is_multiple_of(2) {
+        return Err(MyError::UnpairedName(names[names.len() - 1].clone()));
+    }
+    Ok(names
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut oribit_map = OrbitMap::new();
     oribit_map.add_lines(&mut std::fs::read_to_string("input.txt")?.lines())?;
@@ -163,6 +340,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         "transfer distance: {}",
         oribit_map.find_distance_between("YOU", "SAN")?
     );
+    let (up, down, meeting) = oribit_map.find_transfer_legs("YOU", "SAN")?;
+    println!("transfers: {} up to {}, then {} down", up, meeting, down);
+    if aoc_args::has_flag(std::env::args(), "--stats") {
+        println!("{:?}", oribit_map.stats());
+    }
+    let pairs = distance_pairs(&aoc_args::flag_values(std::env::args(), "--distances"))?;
+    if !pairs.is_empty() {
+        let index = DistanceIndex::new(&oribit_map)?;
+        for (from, to) in pairs {
+            println!(
+                "distance from {} to {}: {}",
+                from,
+                to,
+                index.find_distance_between(&from, &to)?
+            );
+        }
+    }
+    if let Some(name) = aoc_args::flag_value(std::env::args(), "--ancestors") {
+        println!(
+            "ancestors of {}: {}",
+            name,
+            oribit_map.ancestors(&name)?.join(", ")
+        );
+    }
     Ok(())
 }
 
@@ -202,6 +403,24 @@ K)L";
         map.add_lines(&mut map_text.lines())
             .expect("failed to add lines");
         assert_eq!(42, map.total_number_of_orbits().expect("failed to count"));
+        assert_eq!(
+            OrbitStats {
+                object_count: 12,
+                max_depth: Some(7),
+                valid: true,
+            },
+            map.stats()
+        );
+
+        map.add_orbit("X", "Y").expect("failed to add entry");
+        assert_eq!(
+            OrbitStats {
+                object_count: 14,
+                max_depth: None,
+                valid: false,
+            },
+            map.stats()
+        );
     }
 
     #[test]
@@ -223,5 +442,185 @@ I)SAN";
         map.add_lines(&mut map_text.lines())
             .expect("failed to add lines");
         assert_eq!(4, map.find_distance_between("YOU", "SAN").unwrap());
+
+        let index = DistanceIndex::new(&map).expect("failed to build index");
+        assert_eq!(4, index.find_distance_between("YOU", "SAN").unwrap());
+        assert_eq!(6, index.find_distance_between("L", "H").unwrap());
+        let leaves = ["F", "H", "L", "YOU", "SAN"];
+        for a in leaves.iter() {
+            for b in leaves.iter().filter(|b| *b != a) {
+                assert_eq!(
+                    map.find_distance_between(a, b).unwrap(),
+                    index.find_distance_between(a, b).unwrap()
+                );
+            }
+        }
+        match index.find_distance_between("YOU", "NOBODY") {
+            Err(MyError::NodeNotFound) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distance_index_ancestors() {
+        let map_text = "COM)B
+B)C
+C)D
+D)E
+E)J
+J)K
+K)YOU
+D)I
+I)SAN";
+        let mut map = OrbitMap::new();
+        map.add_lines(&mut map_text.lines())
+            .expect("failed to add lines");
+        let index = DistanceIndex::new(&map).expect("failed to build index");
+        assert_eq!(4, index.find_distance_between("YOU", "SAN").unwrap());
+        assert_eq!(1, index.find_distance_between("K", "YOU").unwrap());
+        assert_eq!(1, index.find_distance_between("YOU", "K").unwrap());
+        assert_eq!(4, index.find_distance_between("D", "YOU").unwrap());
+        assert_eq!(4, index.find_distance_between("YOU", "D").unwrap());
+        assert_eq!(0, index.find_distance_between("YOU", "YOU").unwrap());
+        match index.find_distance_between("COM", "YOU") {
+            Err(MyError::NoParent) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        const DEPTH: usize = 100_000;
+        let mut map = OrbitMap::new();
+        map.add_orbit("COM", "N0").expect("failed to add entry");
+        for i in 1..DEPTH {
+            map.add_orbit(&format!("N{}", i - 1), &format!("N{}", i))
+                .expect("failed to add entry");
+        }
+        map.add_orbit(&format!("N{}", DEPTH - 1), "YOU")
+            .expect("failed to add entry");
+        map.add_orbit("N0", "SAN").expect("failed to add entry");
+
+        let chain_orbits = DEPTH * (DEPTH + 1) / 2;
+        assert_eq!(
+            chain_orbits + (DEPTH + 1) + 2,
+            map.total_number_of_orbits().expect("failed to count")
+        );
+        assert_eq!(DEPTH - 1, map.find_distance_between("YOU", "SAN").unwrap());
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let map_text = "COM)B
+B)C
+C)D
+D)E
+E)F
+B)G
+G)H
+D)I
+E)J
+J)K
+K)L";
+        let mut map = OrbitMap::new();
+        map.add_lines(&mut map_text.lines())
+            .expect("failed to add lines");
+        assert_eq!(
+            vec!["K", "J", "E", "D", "C", "B", "COM"],
+            map.ancestors("L").expect("failed to get ancestors")
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            map.ancestors("COM").expect("failed to get ancestors")
+        );
+        map.ancestors("Z").expect_err("should not find Z");
+    }
+
+    #[test]
+    fn test_transfer_legs() {
+        let map_text = "COM)B
+B)C
+C)D
+D)E
+E)F
+B)G
+G)H
+D)I
+E)J
+J)K
+K)L
+K)YOU
+I)SAN";
+        let mut map = OrbitMap::new();
+        map.add_lines(&mut map_text.lines())
+            .expect("failed to add lines");
+        let (up, down, meeting) = map.find_transfer_legs("YOU", "SAN").unwrap();
+        assert_eq!((3, 1, "D"), (up, down, meeting.as_str()));
+        assert_eq!(4, up + down);
+    }
+
+    #[test]
+    fn test_transfer_legs_ancestors() {
+        let map_text = "COM)B
+B)C
+C)D
+D)E
+E)J
+J)K
+K)YOU
+D)I
+I)SAN";
+        let mut map = OrbitMap::new();
+        map.add_lines(&mut map_text.lines())
+            .expect("failed to add lines");
+        let legs = |a, b| map.find_transfer_legs(a, b).unwrap();
+        assert_eq!((0, 4, "C".to_string()), legs("D", "YOU"));
+        assert_eq!((4, 0, "C".to_string()), legs("YOU", "D"));
+        assert_eq!((0, 1, "J".to_string()), legs("K", "YOU"));
+        assert_eq!((0, 0, "K".to_string()), legs("YOU", "YOU"));
+        match map.find_transfer_legs("COM", "YOU") {
+            Err(MyError::NoParent) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let index = DistanceIndex::new(&map).expect("failed to build index");
+        for (a, b) in [("D", "YOU"), ("K", "YOU"), ("YOU", "SAN"), ("YOU", "YOU")].iter() {
+            assert_eq!(
+                map.find_distance_between(a, b).unwrap(),
+                index.find_distance_between(a, b).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_error_line() {
+        let mut map = OrbitMap::new();
+        match map.add_lines(&mut "COM)B\nB)C\nCD\nD)E".lines()) {
+            Err(MyError::ParseError { line, content }) => {
+                assert_eq!(3, line);
+                assert_eq!("CD", content);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_distance_pairs() {
+        let names: Vec<String> = vec!["YOU", "SAN", "L", "H"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            vec![
+                ("YOU".to_string(), "SAN".to_string()),
+                ("L".to_string(), "H".to_string())
+            ],
+            distance_pairs(&names).unwrap()
+        );
+        assert!(distance_pairs(&names[..0]).unwrap().is_empty());
+        match distance_pairs(&names[..3]) {
+            Err(MyError::UnpairedName(name)) => assert_eq!("L", name),
+            other => panic!("expected unpaired name, got {:?}", other),
+        }
     }
 }
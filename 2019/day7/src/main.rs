@@ -1,7 +1,10 @@
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::error::Error;
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
 use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
 
 extern crate intcode;
 
@@ -9,7 +12,7 @@ extern crate intcode;
 //TODO: make this a generator?
 fn permutation(begin: i64, number_of_perms: u64) -> Vec<Vec<i64>> {
     fn inner(result: &mut Vec<Vec<i64>>, prefix: &Vec<i64>, to_perm: &Vec<i64>) {
-        if to_perm.len() == 0 {
+        if to_perm.is_empty() {
             result.push(prefix.to_owned());
         } else {
             for i in 0..to_perm.len() {
@@ -32,12 +35,21 @@ fn permutation(begin: i64, number_of_perms: u64) -> Vec<Vec<i64>> {
     if number_of_perms != 0 {
         inner(&mut ret, &Vec::new(), &to_perm);
     }
+    debug_assert_eq!(
+        if number_of_perms == 0 {
+            0
+        } else {
+            (1..=number_of_perms as usize).product::<usize>()
+        },
+        ret.len()
+    );
+    debug_assert_eq!(ret.len(), ret.iter().collect::<HashSet<_>>().len());
     ret
 }
 
 fn run_amplifier_controller_program(
-    program: &Vec<i64>,
-    phase_setting: &Vec<i64>,
+    program: &[i64],
+    phase_setting: &[i64],
 ) -> Result<i64, Box<dyn Error>> {
     let mut input: i64 = 0;
     for phase in phase_setting {
@@ -55,14 +67,34 @@ fn run_amplifier_controller_program(
     Ok(input)
 }
 
-fn find_max_thruster(program: &Vec<i64>) -> Result<i64, Box<dyn Error>> {
-    let mut max_thrust = i64::min_value();
+//Tries every ordering of the n phases starting at phase_start, with or without
+//the feedback loop. Returns the max thrust and the phase setting that produced it.
+fn find_max(
+    program: &[i64],
+    phase_start: i64,
+    n: u64,
+    feedback: bool,
+) -> Result<(i64, Vec<i64>), Box<dyn Error>> {
+    let mut max_thrust = i64::MIN;
+    let mut best_phase_setting = Vec::new();
 
-    for phase_setting in permutation(0, 5) {
-        max_thrust = max_thrust.max(run_amplifier_controller_program(program, &phase_setting)?);
+    for phase_setting in permutation(phase_start, n) {
+        let this_thrust = if feedback {
+            run_amplifier_controller_program_feedback(program, &phase_setting)?
+        } else {
+            run_amplifier_controller_program(program, &phase_setting)?
+        };
+        if this_thrust > max_thrust {
+            max_thrust = this_thrust;
+            best_phase_setting = phase_setting;
+        }
     }
 
-    Ok(max_thrust)
+    Ok((max_thrust, best_phase_setting))
+}
+
+fn find_max_thruster(program: &[i64]) -> Result<(i64, Vec<i64>), Box<dyn Error>> {
+    find_max(program, 0, 5, false)
 }
 
 fn run_amplifier_controller_program_part(
@@ -93,16 +125,58 @@ fn pump_feedback(
     }
 }
 
+//How long to wait for all the amplifiers to halt before giving up.
+//A bad program can deadlock the feedback loop, for example by having every
+//amplifier wait for input at the same time.
+const FEEDBACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+//How many values each channel in the feedback loop can hold before the sender blocks.
+//The channel into the first amplifier is preloaded with both its phase setting and
+//the initial 0 before any thread starts receiving, so anything below 2 would
+//deadlock during setup, before the timeout applies, and is rejected. Once running,
+//each amplifier only has one value in flight, so larger buffers just let the
+//amplifiers get further ahead of each other.
+const FEEDBACK_CHANNEL_BUFFER: usize = 10;
+
+fn join_amplifiers(
+    amplifiers: Vec<JoinHandle<Result<(), intcode::IntcodeError>>>,
+    pumper: JoinHandle<Result<i64, intcode::IntcodeError>>,
+) -> Result<i64, intcode::IntcodeError> {
+    for jh in amplifiers {
+        jh.join().unwrap()?;
+    }
+    pumper.join().unwrap()
+}
+
 fn run_amplifier_controller_program_feedback(
-    program: &Vec<i64>,
-    phase_setting: &Vec<i64>,
+    program: &[i64],
+    phase_setting: &[i64],
+) -> Result<i64, intcode::IntcodeError> {
+    run_amplifier_controller_program_feedback_with_timeout(
+        program,
+        phase_setting,
+        FEEDBACK_TIMEOUT,
+        FEEDBACK_CHANNEL_BUFFER,
+    )
+}
+
+fn run_amplifier_controller_program_feedback_with_timeout(
+    program: &[i64],
+    phase_setting: &[i64],
+    timeout: Duration,
+    channel_buffer: usize,
 ) -> Result<i64, intcode::IntcodeError> {
-    let (feedback_front_send, feedback_front_recv) = sync_channel::<i64>(10);
-    let (send1, recv1) = sync_channel::<i64>(10);
-    let (send2, recv2) = sync_channel::<i64>(10);
-    let (send3, recv3) = sync_channel::<i64>(10);
-    let (send4, recv4) = sync_channel::<i64>(10);
-    let (feedback_back_send, feedback_back_recv) = sync_channel::<i64>(10);
+    assert!(
+        channel_buffer >= 2,
+        "feedback channels must hold at least 2 values, got {}",
+        channel_buffer
+    );
+    let (feedback_front_send, feedback_front_recv) = sync_channel::<i64>(channel_buffer);
+    let (send1, recv1) = sync_channel::<i64>(channel_buffer);
+    let (send2, recv2) = sync_channel::<i64>(channel_buffer);
+    let (send3, recv3) = sync_channel::<i64>(channel_buffer);
+    let (send4, recv4) = sync_channel::<i64>(channel_buffer);
+    let (feedback_back_send, feedback_back_recv) = sync_channel::<i64>(channel_buffer);
     feedback_front_send.send(phase_setting[0])?;
     feedback_front_send.send(0)?;
     send1.send(phase_setting[1])?;
@@ -126,32 +200,35 @@ fn run_amplifier_controller_program_feedback(
     });
     let pumper = thread::spawn(move || pump_feedback(feedback_back_recv, feedback_front_send));
 
-    jh1.join().unwrap()?;
-    jh2.join().unwrap()?;
-    jh3.join().unwrap()?;
-    jh4.join().unwrap()?;
-    jh5.join().unwrap()?;
-    Ok(pumper.join().unwrap()?)
-}
-
-fn find_max_thruster_feedback(program: &Vec<i64>) -> Result<i64, Box<dyn Error>> {
-    let mut max_thrust = i64::min_value();
-
-    for phase_setting in permutation(5, 5) {
-        let this_thrust = run_amplifier_controller_program_feedback(program, &phase_setting)?;
-        max_thrust = max_thrust.max(this_thrust);
+    //Join on another thread so we can stop waiting after the timeout.
+    //If the timeout fires, the amplifier threads are left running.
+    let (done_send, done_recv) = sync_channel(1);
+    thread::spawn(move || {
+        let _ = done_send.send(join_amplifiers(vec![jh1, jh2, jh3, jh4, jh5], pumper));
+    });
+    match done_recv.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(intcode::IntcodeError::Timeout),
+        Err(RecvTimeoutError::Disconnected) => panic!("amplifier thread panicked"),
     }
+}
 
-    Ok(max_thrust)
+fn find_max_thruster_feedback(program: &[i64]) -> Result<(i64, Vec<i64>), Box<dyn Error>> {
+    find_max(program, 5, 5, true)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let program = intcode::parse_program(&std::fs::read_to_string("input.txt")?)?;
 
-    println!("max thrust - part1: {}", find_max_thruster(&program)?);
+    let (thrust, phase_setting) = find_max_thruster(&program)?;
+    println!(
+        "max thrust - part1: {} (phase setting {:?})",
+        thrust, phase_setting
+    );
+    let (thrust, phase_setting) = find_max_thruster_feedback(&program)?;
     println!(
-        "max thrust - part2: {}",
-        find_max_thruster_feedback(&program)?
+        "max thrust - part2: {} (phase setting {:?})",
+        thrust, phase_setting
     );
 
     Ok(())
@@ -161,6 +238,10 @@ fn main() -> Result<(), Box<dyn Error>> {
 mod test {
     use super::*;
 
+    //The answers for this crate's input.txt; see test_all.sh.
+    const PART1_ANSWER: i64 = 21760;
+    const PART2_ANSWER: i64 = 69816958;
+
     #[test]
     fn test_perm() {
         let empty: Vec<Vec<i64>> = Vec::new();
@@ -180,6 +261,14 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_perm_count() {
+        let perms = permutation(0, 5);
+        assert_eq!(120, perms.len());
+        assert_eq!(120, perms.iter().collect::<HashSet<_>>().len());
+        assert_eq!(120, permutation(5, 5).len());
+    }
+
     #[test]
     fn test_perm_oft() {
         let empty: Vec<Vec<i64>> = Vec::new();
@@ -203,16 +292,25 @@ mod test {
     fn test_find_max_thrust() {
         let program = intcode::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0")
             .expect("parse program");
-        assert_eq!(43210, find_max_thruster(&program).expect("find max thrust"));
+        assert_eq!(
+            (43210, vec![4, 3, 2, 1, 0]),
+            find_max_thruster(&program).expect("find max thrust")
+        );
 
         let program = intcode::parse_program(
             "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
         )
         .expect("parse program");
-        assert_eq!(54321, find_max_thruster(&program).expect("find max thrust"));
+        assert_eq!(
+            (54321, vec![0, 1, 2, 3, 4]),
+            find_max_thruster(&program).expect("find max thrust")
+        );
 
         let program = intcode::parse_program("3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0").expect("parse program");
-        assert_eq!(65210, find_max_thruster(&program).expect("find max thrust"));
+        assert_eq!(
+            (65210, vec![1, 0, 4, 3, 2]),
+            find_max_thruster(&program).expect("find max thrust")
+        );
     }
 
     #[test]
@@ -222,7 +320,7 @@ mod test {
         )
         .expect("parse program");
         assert_eq!(
-            139629729,
+            (139629729, vec![9, 8, 7, 6, 5]),
             find_max_thruster_feedback(&program).expect("find max thrust")
         );
 
@@ -231,8 +329,123 @@ mod test {
         )
         .expect("parse program");
         assert_eq!(
-            18216,
+            (18216, vec![9, 7, 8, 5, 6]),
             find_max_thruster_feedback(&program).expect("find max thrust")
         );
     }
+
+    #[test]
+    fn test_winning_phase_setting_reproduces_thrust() {
+        let program = intcode::parse_program(
+            "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+        )
+        .expect("parse program");
+        let (thrust, phase_setting) = find_max_thruster(&program).expect("find max thrust");
+        assert_eq!(
+            thrust,
+            run_amplifier_controller_program(&program, &phase_setting).expect("run program")
+        );
+
+        let program = intcode::parse_program(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        )
+        .expect("parse program");
+        let (thrust, phase_setting) =
+            find_max_thruster_feedback(&program).expect("find max thrust");
+        assert_eq!(
+            thrust,
+            run_amplifier_controller_program_feedback(&program, &phase_setting)
+                .expect("run program")
+        );
+    }
+
+    #[test]
+    fn test_feedback_timeout() {
+        //Reads input forever without producing output, so every amplifier
+        //ends up waiting on the one before it.
+        let program = intcode::parse_program("3,0,1105,1,0").expect("parse program");
+        match run_amplifier_controller_program_feedback_with_timeout(
+            &program,
+            &[5, 6, 7, 8, 9],
+            Duration::from_millis(100),
+            FEEDBACK_CHANNEL_BUFFER,
+        ) {
+            Err(intcode::IntcodeError::Timeout) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "feedback channels must hold at least 2 values")]
+    fn test_feedback_channel_buffer_too_small() {
+        let program = intcode::parse_program("99").expect("parse program");
+        let _ = run_amplifier_controller_program_feedback_with_timeout(
+            &program,
+            &[5, 6, 7, 8, 9],
+            FEEDBACK_TIMEOUT,
+            1,
+        );
+    }
+
+    #[test]
+    fn test_feedback_minimum_channel_buffer() {
+        let program = intcode::parse_program(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        )
+        .expect("parse program");
+        //2 is the smallest buffer that does not deadlock, see FEEDBACK_CHANNEL_BUFFER.
+        assert_eq!(
+            139629729,
+            run_amplifier_controller_program_feedback_with_timeout(
+                &program,
+                &[9, 8, 7, 6, 5],
+                FEEDBACK_TIMEOUT,
+                2,
+            )
+            .expect("run program")
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_real_input() {
+        let program = intcode::parse_program(
+            &std::fs::read_to_string("input.txt").expect("failed to read input.txt"),
+        )
+        .expect("parse program");
+        assert_eq!(
+            PART1_ANSWER,
+            find_max_thruster(&program).expect("find max thrust").0
+        );
+        assert_eq!(
+            PART2_ANSWER,
+            find_max_thruster_feedback(&program)
+                .expect("find max thrust")
+                .0
+        );
+    }
+
+    #[test]
+    fn test_find_max() {
+        let program = intcode::parse_program("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0")
+            .expect("parse program");
+        assert_eq!(
+            (43210, vec![4, 3, 2, 1, 0]),
+            find_max(&program, 0, 5, false).expect("find max thrust")
+        );
+        //With only three amplifiers, the best is to put the largest phase first.
+        assert_eq!(
+            (432, vec![4, 3, 2]),
+            find_max(&program, 2, 3, false).expect("find max thrust")
+        );
+
+        let program = intcode::parse_program(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        )
+        .expect("parse program");
+        assert_eq!(
+            (139629729, vec![9, 8, 7, 6, 5]),
+            find_max(&program, 5, 5, true).expect("find max thrust")
+        );
+    }
 }
@@ -1,10 +1,29 @@
 use std::error::Error;
 use std::fmt;
 
+extern crate aoc_args;
+
 #[derive(Debug)]
 enum ErrorCodes {
     WrongDimension,
     FileParseFailure,
+    UndefinedPixels(Vec<(usize, usize)>),
+    ZeroDimension,
+    //The number of pixels is zero or does not divide evenly into layers.
+    IncompleteLayer {
+        pixel_count: usize,
+        pixels_per_layer: usize,
+    },
+    //The number of pixels does not divide evenly into images.
+    IncompleteImage {
+        pixel_count: usize,
+        pixels_per_image: usize,
+    },
+    //The image size is zero or not a whole number of layers.
+    ImageSizeNotLayerMultiple {
+        pixels_per_image: usize,
+        pixels_per_layer: usize,
+    },
 }
 
 impl fmt::Display for ErrorCodes {
@@ -12,6 +31,34 @@ impl fmt::Display for ErrorCodes {
         match self {
             ErrorCodes::WrongDimension => write!(f, "wrong dimension"),
             ErrorCodes::FileParseFailure => write!(f, "file parse failure"),
+            ErrorCodes::UndefinedPixels(pixels) => {
+                write!(f, "{} pixels are transparent in every layer", pixels.len())
+            }
+            ErrorCodes::ZeroDimension => write!(f, "width and height must be nonzero"),
+            ErrorCodes::IncompleteLayer {
+                pixel_count,
+                pixels_per_layer,
+            } => write!(
+                f,
+                "{} pixels is not a whole number of {} pixel layers",
+                pixel_count, pixels_per_layer
+            ),
+            ErrorCodes::IncompleteImage {
+                pixel_count,
+                pixels_per_image,
+            } => write!(
+                f,
+                "{} pixels is not a whole number of {} pixel images",
+                pixel_count, pixels_per_image
+            ),
+            ErrorCodes::ImageSizeNotLayerMultiple {
+                pixels_per_image,
+                pixels_per_layer,
+            } => write!(
+                f,
+                "image size of {} pixels is not a multiple of the {} pixel layer size",
+                pixels_per_image, pixels_per_layer
+            ),
         }
     }
 }
@@ -23,20 +70,60 @@ fn parse_image_layers(
     height: usize,
     input: &str,
 ) -> Result<Vec<Vec<u8>>, ErrorCodes> {
+    if width == 0 || height == 0 {
+        return Err(ErrorCodes::ZeroDimension);
+    }
     let pixles_per_layer = width * height;
 
     let mut all_bytes: Vec<u8> = vec![];
     for ch in input.chars() {
-        if let Ok(num) = u8::from_str_radix(&ch.to_string(), 10) {
+        if let Ok(num) = ch.to_string().parse::<u8>() {
             all_bytes.push(num);
         } else {
             return Err(ErrorCodes::FileParseFailure);
         }
     }
 
+    if all_bytes.is_empty() || !all_bytes.len().is_multiple_of(pixles_per_layer) {
+        return Err(ErrorCodes::IncompleteLayer {
+            pixel_count: all_bytes.len(),
+            pixels_per_layer: pixles_per_layer,
+        });
+    }
+
     Ok(all_bytes
         .chunks_exact(pixles_per_layer)
-        .map(|chunck| chunck.iter().map(|c| *c).collect::<Vec<u8>>())
+        .map(|chunck| chunck.to_vec())
+        .collect())
+}
+
+//Splits input holding several concatenated images, each made of
+//pixels_per_image pixels, into the layers of each image.
+fn parse_all_images(
+    width: usize,
+    height: usize,
+    pixels_per_image: usize,
+    input: &str,
+) -> Result<Vec<Vec<Vec<u8>>>, ErrorCodes> {
+    let layers = parse_image_layers(width, height, input)?;
+    let pixels_per_layer = width * height;
+    let pixel_count = layers.len() * pixels_per_layer;
+    if pixels_per_image == 0 || !pixels_per_image.is_multiple_of(pixels_per_layer) {
+        return Err(ErrorCodes::ImageSizeNotLayerMultiple {
+            pixels_per_image,
+            pixels_per_layer,
+        });
+    }
+    if !pixel_count.is_multiple_of(pixels_per_image) {
+        return Err(ErrorCodes::IncompleteImage {
+            pixel_count,
+            pixels_per_image,
+        });
+    }
+
+    Ok(layers
+        .chunks_exact(pixels_per_image / pixels_per_layer)
+        .map(|image| image.to_vec())
         .collect())
 }
 
@@ -51,55 +138,262 @@ fn count_colors(layer: &Vec<u8>) -> ColorOccurences {
         colors[*color as usize] += 1;
     }
 
-    ColorOccurences { colors: colors }
+    ColorOccurences { colors }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let width = 25;
-    let height = 6;
-    let input = std::fs::read_to_string("input.txt")?;
-
-    let layers = parse_image_layers(width, height, &input)?;
+//Returns the first layer with the fewest pixels of the given color.
+fn layer_with_fewest(layers: &[Vec<u8>], color: u8) -> Option<&Vec<u8>> {
+    layers
+        .iter()
+        .min_by_key(|layer| count_colors(layer).colors[color as usize])
+}
 
-    let mut least_zeros = usize::MAX;
-    let mut one_times_two = 0;
+const TRANSPARENT: u8 = 2;
 
-    for layer in layers.iter().map(|l| count_colors(&l)) {
-        if layer.colors[0] < least_zeros {
-            least_zeros = layer.colors[0];
-            one_times_two = layer.colors[1] * layer.colors[2];
-        }
+//Stacks the layers, with the first layer in front. A pixel that is transparent
+//in every layer is reported as an error, since it has no color to render.
+fn flatten_layers(layers: &[Vec<u8>], width: usize, height: usize) -> Result<Vec<u8>, ErrorCodes> {
+    if layers.iter().any(|layer| layer.len() != width * height) {
+        return Err(ErrorCodes::WrongDimension);
     }
 
-    println!("{}", one_times_two);
-
-    let decoded_images: Vec<u8> = vec![255; width * height]; //arbitrary high color
-    let decoded_images = layers.iter().rev().fold(decoded_images, |img, layer| {
-        return img
-            .iter()
+    let decoded_images: Vec<u8> = vec![TRANSPARENT; width * height];
+    let decoded_images: Vec<u8> = layers.iter().rev().fold(decoded_images, |img, layer| {
+        img.iter()
             .zip(layer.iter())
             .map(|(c_img, c_layer)| match *c_layer {
                 0 => 0,
                 1 => 1,
-                2 => *c_img,
+                TRANSPARENT => *c_img,
                 _ => panic!(),
             })
-            .collect();
+            .collect()
     });
 
-    for line in decoded_images.chunks_exact(width) {
+    let undefined: Vec<(usize, usize)> = decoded_images
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c == TRANSPARENT)
+        .map(|(ndx, _)| (ndx % width, ndx / width))
+        .collect();
+    if undefined.is_empty() {
+        Ok(decoded_images)
+    } else {
+        Err(ErrorCodes::UndefinedPixels(undefined))
+    }
+}
+
+//Returns the flattened image as rows of pixels.
+fn decode_image(
+    layers: &[Vec<u8>],
+    width: usize,
+    height: usize,
+) -> Result<Vec<Vec<u8>>, ErrorCodes> {
+    Ok(flatten_layers(layers, width, height)?
+        .chunks_exact(width)
+        .map(|row| row.to_vec())
+        .collect())
+}
+
+fn render_image(image: &[Vec<u8>], on: char, off: char) -> String {
+    let mut ret = String::new();
+    for line in image {
         for ch in line.iter() {
-            print!(
-                "{}",
-                match ch {
-                    0 => ' ',
-                    1 => '#',
-                    _ => panic!(),
-                }
-            );
+            ret.push(match ch {
+                0 => off,
+                1 => on,
+                _ => panic!(),
+            });
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+//Draws a white pixel as a bright white block using an ANSI escape sequence.
+const COLOR_PIXEL: &str = "\x1b[97m\u{2588}\x1b[0m";
+
+//Like render_image, but draws white pixels as a colored block, which is
+//easier to read in a terminal.
+fn render_image_color(image: &[Vec<u8>], off: char) -> String {
+    let mut ret = String::new();
+    for line in image {
+        for ch in line.iter() {
+            match ch {
+                0 => ret.push(off),
+                1 => ret.push_str(COLOR_PIXEL),
+                _ => panic!(),
+            }
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+//Returns the first character of the argument following the named flag.
+fn char_arg(args: &[String], name: &str, default: char) -> char {
+    aoc_args::flag_value(args, name)
+        .and_then(|value| value.chars().next())
+        .unwrap_or(default)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let width = 25;
+    let height = 6;
+    let input = std::fs::read_to_string("input.txt")?;
+
+    let layers = parse_image_layers(width, height, &input)?;
+
+    //parse_image_layers rejects input without at least one full layer.
+    let layer = count_colors(layer_with_fewest(&layers, 0).unwrap());
+    println!("{}", layer.colors[1] * layer.colors[2]);
+
+    let args: Vec<String> = std::env::args().collect();
+    let on = char_arg(&args, "--on", '#');
+    let off = char_arg(&args, "--off", ' ');
+    let color = aoc_args::has_flag(&args, "--color");
+
+    //By default the whole input is a single image; --image-pixels splits it
+    //into several concatenated images, such as the frames of an animation.
+    let images = match aoc_args::flag_value(&args, "--image-pixels") {
+        Some(pixels) => parse_all_images(width, height, pixels.parse()?, &input)?,
+        None => vec![layers],
+    };
+    for image in images {
+        let decoded_image = decode_image(&image, width, height)?;
+        if color {
+            print!("{}", render_image_color(&decoded_image, off));
+        } else {
+            print!("{}", render_image(&decoded_image, on, off));
         }
-        println!();
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zero_dimension() {
+        match parse_image_layers(0, 2, "0222") {
+            Err(ErrorCodes::ZeroDimension) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_incomplete_layer() {
+        match parse_image_layers(2, 2, "022211") {
+            Err(ErrorCodes::IncompleteLayer {
+                pixel_count: 6,
+                pixels_per_layer: 4,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse_image_layers(2, 2, "") {
+            Err(ErrorCodes::IncompleteLayer { pixel_count: 0, .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_all_images() {
+        let images = parse_all_images(2, 2, 8, "0212110122201011").expect("failed to parse");
+        assert_eq!(2, images.len());
+        assert_eq!(
+            vec![vec![0, 1], vec![1, 1]],
+            decode_image(&images[0], 2, 2).expect("failed to decode")
+        );
+        assert_eq!(
+            vec![vec![1, 0], vec![1, 0]],
+            decode_image(&images[1], 2, 2).expect("failed to decode")
+        );
+        match parse_all_images(2, 2, 12, "0222112222120000") {
+            Err(ErrorCodes::IncompleteImage {
+                pixel_count: 16,
+                pixels_per_image: 12,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse_all_images(2, 2, 6, "0222112222120000") {
+            Err(ErrorCodes::ImageSizeNotLayerMultiple {
+                pixels_per_image: 6,
+                pixels_per_layer: 4,
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse_all_images(2, 2, 0, "0222112222120000") {
+            Err(ErrorCodes::ImageSizeNotLayerMultiple {
+                pixels_per_image: 0,
+                ..
+            }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_layer_with_fewest() {
+        let layers = parse_image_layers(3, 2, "123456789012").expect("failed to parse");
+        let layer = layer_with_fewest(&layers, 0).expect("no layers");
+        assert_eq!(&vec![1, 2, 3, 4, 5, 6], layer);
+        let counts = count_colors(layer);
+        assert_eq!(1, counts.colors[1] * counts.colors[2]);
+        assert_eq!(&layers[1], layer_with_fewest(&layers, 3).unwrap());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let layers = parse_image_layers(2, 2, "0222112222120000").expect("failed to parse");
+        assert_eq!(
+            vec![0, 1, 1, 0],
+            flatten_layers(&layers, 2, 2).expect("failed to flatten")
+        );
+    }
+
+    #[test]
+    fn test_decode_image() {
+        let layers = parse_image_layers(2, 2, "0222112222120000").expect("failed to parse");
+        assert_eq!(
+            vec![vec![0, 1], vec![1, 0]],
+            decode_image(&layers, 2, 2).expect("failed to decode")
+        );
+    }
+
+    #[test]
+    fn test_flatten_undefined_pixels() {
+        let layers = parse_image_layers(2, 2, "02022202").expect("failed to parse");
+        match flatten_layers(&layers, 2, 2) {
+            Err(ErrorCodes::UndefinedPixels(pixels)) => assert_eq!(vec![(1, 0), (1, 1)], pixels),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_palette() {
+        let image = vec![vec![0, 1], vec![1, 0]];
+        assert_eq!(" #\n# \n", render_image(&image, '#', ' '));
+        assert_eq!(".@\n@.\n", render_image(&image, '@', '.'));
+    }
+
+    #[test]
+    fn test_render_color() {
+        let image = vec![vec![0, 1], vec![0, 0]];
+        assert_eq!(
+            " \x1b[97m\u{2588}\x1b[0m\n  \n",
+            render_image_color(&image, ' ')
+        );
+    }
+
+    #[test]
+    fn test_char_arg() {
+        let args: Vec<String> = vec!["day8", "--on", "@", "--off"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!('@', char_arg(&args, "--on", '#'));
+        assert_eq!(' ', char_arg(&args, "--off", ' '));
+        assert_eq!('x', char_arg(&args, "--other", 'x'));
+    }
+}
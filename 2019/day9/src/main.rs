@@ -1,13 +1,30 @@
-use std::error::Error;
-
 extern crate intcode;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut mem = intcode::parse_program(&std::fs::read_to_string("input.txt")?)?;
-    intcode::execute(
-        &mut mem,
-        &mut std::io::stdin().lock(),
-        &mut std::io::stdout().lock(),
-    )?;
-    Ok(())
+fn main() -> Result<(), intcode::IntcodeError> {
+    intcode::run_file("input.txt", std::env::args().nth(1).as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    //The answers for this crate's input.txt; see test_all.sh.
+    const PART1_ANSWER: &str = "2662308295\n";
+    const PART2_ANSWER: &str = "63441\n";
+
+    #[test]
+    #[ignore]
+    fn test_real_input_part1() {
+        assert_eq!(
+            PART1_ANSWER,
+            intcode::run_file_to_string("input.txt", "1\n").unwrap()
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_real_input_part2() {
+        assert_eq!(
+            PART2_ANSWER,
+            intcode::run_file_to_string("input.txt", "2\n").unwrap()
+        );
+    }
 }
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
-use std::convert::TryFrom;
+use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::error::Error;
 use std::fmt;
 use std::io::BufRead;
+use std::io::Read;
 use std::io::Write;
 use std::num::ParseIntError;
 use std::sync::mpsc::{Receiver, RecvError, SendError, SyncSender};
@@ -15,6 +16,8 @@ pub enum IntcodeError {
     InvalidOpCode(i64),
     IndexOutOfRange,
     EOF,
+    NoInputAvailable,
+    Timeout,
     IntParse(ParseIntError),
     IoError(std::io::Error),
     RecvError(RecvError),
@@ -55,6 +58,10 @@ impl fmt::Display for IntcodeError {
             IntcodeError::InvalidOpCode(invalid) => write!(f, "invalid opcode: {}", invalid),
             IntcodeError::IndexOutOfRange => write!(f, "index out of range"),
             IntcodeError::EOF => write!(f, "EOF"),
+            IntcodeError::NoInputAvailable => {
+                write!(f, "program tried to read input, but no input is available")
+            }
+            IntcodeError::Timeout => write!(f, "timed out"),
             IntcodeError::IntParse(int_parse_error) => write!(f, "Int parse: {}", int_parse_error),
             IntcodeError::IoError(io_err) => write!(f, "io error: {}", io_err),
             IntcodeError::RecvError(recv_err) => write!(f, "recv error: {}", recv_err),
@@ -63,10 +70,21 @@ impl fmt::Display for IntcodeError {
     }
 }
 
+//Anything after a '#' on a line is treated as a comment.
+//Whitespace around the commas is ignored.
 pub fn parse_program(input: &str) -> Result<Vec<i64>, IntcodeError> {
+    let without_comments: String = input
+        .lines()
+        .map(|line| match line.find('#') {
+            Some(ndx) => &line[..ndx],
+            None => line,
+        })
+        .collect::<Vec<&str>>()
+        .join("\n");
+
     let mut v: Vec<i64> = Vec::new();
-    for num_str in input.trim().split(',') {
-        if let Ok(num) = i64::from_str_radix(num_str, 10) {
+    for num_str in without_comments.trim().split(',') {
+        if let Ok(num) = num_str.trim().parse::<i64>() {
             v.push(num);
         } else {
             return Err(IntcodeError::ProgramParseError);
@@ -176,7 +194,7 @@ impl ReadNumber for BufReadNumber<'_> {
         let mut buf = String::new();
         match self.buf_read.read_line(&mut buf)? {
             0 => Err(IntcodeError::EOF),
-            _ => Ok(i64::from_str_radix(&buf.trim(), 10)?),
+            _ => Ok(buf.trim().parse::<i64>()?),
         }
     }
 }
@@ -192,9 +210,21 @@ trait WriteNumber {
     fn prompt_for_number(&mut self) -> Result<(), IntcodeError>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputRadix {
+    Decimal,
+    Hexadecimal,
+    Binary,
+}
+
 struct WriteWriteNumber<'a> {
     output: &'a mut dyn Write,
     prompt: bool,
+    radix: OutputRadix,
+    //When set, outputs are written on one line with this between them,
+    //instead of one output per line.
+    separator: Option<&'a str>,
+    wrote_any: bool,
 }
 
 struct ChannelWriteNumber {
@@ -203,7 +233,22 @@ struct ChannelWriteNumber {
 
 impl WriteNumber for WriteWriteNumber<'_> {
     fn write_number(&mut self, num: i64) -> Result<(), IntcodeError> {
-        writeln!(self.output, "{}", num)?;
+        let sign = if num < 0 { "-" } else { "" };
+        let text = match self.radix {
+            OutputRadix::Decimal => format!("{}", num),
+            OutputRadix::Hexadecimal => format!("{}{:x}", sign, num.unsigned_abs()),
+            OutputRadix::Binary => format!("{}{:b}", sign, num.unsigned_abs()),
+        };
+        match self.separator {
+            None => writeln!(self.output, "{}", text)?,
+            Some(separator) => {
+                if self.wrote_any {
+                    write!(self.output, "{}", separator)?;
+                }
+                write!(self.output, "{}", text)?;
+            }
+        }
+        self.wrote_any = true;
         Ok(())
     }
 
@@ -227,7 +272,87 @@ impl WriteNumber for ChannelWriteNumber {
     }
 }
 
-struct CpuState<'a, R, W>
+//Forwards every output to two writers, for example a consumer and a logger.
+struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> WriteNumber for Tee<A, B>
+where
+    A: WriteNumber,
+    B: WriteNumber,
+{
+    fn write_number(&mut self, num: i64) -> Result<(), IntcodeError> {
+        self.first.write_number(num)?;
+        self.second.write_number(num)
+    }
+
+    fn prompt_for_number(&mut self) -> Result<(), IntcodeError> {
+        self.first.prompt_for_number()?;
+        self.second.prompt_for_number()
+    }
+}
+
+//Reads and writes numbers as raw bytes, for programs that speak ASCII.
+struct ByteReadNumber<'a> {
+    input: &'a mut dyn Read,
+}
+
+struct ByteWriteNumber<'a> {
+    output: &'a mut dyn Write,
+}
+
+impl ReadNumber for ByteReadNumber<'_> {
+    fn read_number(&mut self) -> Result<i64, IntcodeError> {
+        let mut buf = [0u8; 1];
+        match self.input.read(&mut buf)? {
+            0 => Err(IntcodeError::EOF),
+            _ => Ok(buf[0] as i64),
+        }
+    }
+}
+
+impl WriteNumber for ByteWriteNumber<'_> {
+    fn write_number(&mut self, num: i64) -> Result<(), IntcodeError> {
+        if (0..=255).contains(&num) {
+            self.output.write_all(&[num as u8])?;
+        } else {
+            //Programs use values outside of ASCII for answers, so print them as numbers.
+            writeln!(self.output, "{}", num)?;
+        }
+        Ok(())
+    }
+
+    fn prompt_for_number(&mut self) -> Result<(), IntcodeError> {
+        //Make sure any text asking for input is visible before blocking on the read.
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+//Used when the program is not expected to do any IO.
+//Reading is an error and any output is discarded.
+struct NoIo;
+
+impl ReadNumber for NoIo {
+    fn read_number(&mut self) -> Result<i64, IntcodeError> {
+        Err(IntcodeError::NoInputAvailable)
+    }
+}
+
+impl WriteNumber for NoIo {
+    fn write_number(&mut self, _num: i64) -> Result<(), IntcodeError> {
+        Ok(())
+    }
+
+    fn prompt_for_number(&mut self) -> Result<(), IntcodeError> {
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct CpuState<R, W>
 where
     R: ReadNumber,
     W: WriteNumber,
@@ -235,16 +360,21 @@ where
     pc: i64,
     relative_base: i64,
     mem: HashMap<i64, i64>,
-    input: &'a mut R,
-    output: &'a mut W,
+    input: R,
+    output: W,
+}
+
+enum StepResult {
+    Continue,
+    Halted,
 }
 
-impl<R, W> CpuState<'_, R, W>
+impl<R, W> CpuState<R, W>
 where
     R: ReadNumber,
     W: WriteNumber,
 {
-    fn create<'a>(mem: &[i64], input: &'a mut R, output: &'a mut W) -> CpuState<'a, R, W> {
+    fn create(mem: &[i64], input: R, output: W) -> CpuState<R, W> {
         let mut mem_map: HashMap<i64, i64> = HashMap::with_capacity(mem.len()); //TODO: consider using a faster hasher
         for (i, num) in mem.iter().enumerate() {
             mem_map.insert(i.try_into().unwrap(), *num);
@@ -258,6 +388,21 @@ where
         }
     }
 
+    //Lets tests start execution from an arbitrary instruction.
+    #[cfg(test)]
+    fn create_with_registers(
+        mem: &[i64],
+        pc: i64,
+        relative_base: i64,
+        input: R,
+        output: W,
+    ) -> CpuState<R, W> {
+        let mut ret = CpuState::create(mem, input, output);
+        ret.pc = pc;
+        ret.relative_base = relative_base;
+        ret
+    }
+
     fn load_raw(&self, index: i64) -> Result<i64, IntcodeError> {
         if index < 0 {
             Err(IntcodeError::IndexOutOfRange)
@@ -298,90 +443,122 @@ where
         self.store_raw(self.load_effective_address(pc_rel, mode)?, value)
     }
 
+    fn needs_input(&self) -> Result<bool, IntcodeError> {
+        Ok(matches!(
+            parse_instruction(self.load_raw(self.pc)?)?,
+            Opcode::Input(_)
+        ))
+    }
+
+    //Decodes the instruction at pc into the syntax accepted by assemble.
+    fn current_instruction_text(&self) -> Result<String, IntcodeError> {
+        let instruction = self.load_raw(self.pc)?;
+        parse_instruction(instruction)?;
+        let (mnemonic, _, operand_count, _) = MNEMONICS
+            .iter()
+            .find(|(_, opcode, _, _)| *opcode == instruction % 100)
+            .unwrap();
+        let mut operands = Vec::new();
+        let mut mode_divisor = 100;
+        for ndx in 0..*operand_count {
+            let value = self.load_raw(self.pc + 1 + ndx as i64)?;
+            operands.push(match instruction / mode_divisor % 10 {
+                0 => format!("[{}]", value),
+                1 => format!("{}", value),
+                _ if value < 0 => format!("[rb{}]", value),
+                _ => format!("[rb+{}]", value),
+            });
+            mode_divisor *= 10;
+        }
+        if operands.is_empty() {
+            Ok(mnemonic.to_string())
+        } else {
+            Ok(format!("{} {}", mnemonic, operands.join(", ")))
+        }
+    }
+
     fn execute(&mut self) -> Result<(), IntcodeError> {
-        loop {
-            match parse_instruction(self.load_raw(self.pc)?)? {
-                Opcode::Add(src1_mode, src2_mode, dst_mode) => {
-                    self.store(
-                        3,
-                        dst_mode,
-                        self.load(1, src1_mode)? + self.load(2, src2_mode)?,
-                    )?;
-                    self.pc += 4;
-                }
-                Opcode::Multiply(src1_mode, src2_mode, dst_mode) => {
-                    self.store(
-                        3,
-                        dst_mode,
-                        self.load(1, src1_mode)? * self.load(2, src2_mode)?,
-                    )?;
-                    self.pc += 4;
-                }
-                Opcode::Input(dst_mode) => {
-                    self.output.prompt_for_number()?;
-                    let value = self.input.read_number()?;
-                    self.store(1, dst_mode, value)?;
-                    self.pc += 2;
-                }
-                Opcode::Output(src_mode) => {
-                    self.output.write_number(self.load(1, src_mode)?)?;
-                    self.pc += 2;
-                }
-                Opcode::JumpIfTrue(comparand_mode, target_mode) => {
-                    self.pc = if self.load(1, comparand_mode)? != 0 {
-                        match i64::try_from(self.load(2, target_mode)?) {
-                            Ok(loc) => loc,
-                            Err(_) => return Err(IntcodeError::IndexOutOfRange),
-                        }
+        while let StepResult::Continue = self.step()? {}
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<StepResult, IntcodeError> {
+        match parse_instruction(self.load_raw(self.pc)?)? {
+            Opcode::Add(src1_mode, src2_mode, dst_mode) => {
+                self.store(
+                    3,
+                    dst_mode,
+                    self.load(1, src1_mode)? + self.load(2, src2_mode)?,
+                )?;
+                self.pc += 4;
+            }
+            Opcode::Multiply(src1_mode, src2_mode, dst_mode) => {
+                self.store(
+                    3,
+                    dst_mode,
+                    self.load(1, src1_mode)? * self.load(2, src2_mode)?,
+                )?;
+                self.pc += 4;
+            }
+            Opcode::Input(dst_mode) => {
+                self.output.prompt_for_number()?;
+                let value = self.input.read_number()?;
+                self.store(1, dst_mode, value)?;
+                self.pc += 2;
+            }
+            Opcode::Output(src_mode) => {
+                self.output.write_number(self.load(1, src_mode)?)?;
+                self.pc += 2;
+            }
+            Opcode::JumpIfTrue(comparand_mode, target_mode) => {
+                self.pc = if self.load(1, comparand_mode)? != 0 {
+                    self.load(2, target_mode)?
+                } else {
+                    self.pc + 3
+                };
+            }
+            Opcode::JumpIfFalse(comparand_mode, target_mode) => {
+                self.pc = if self.load(1, comparand_mode)? == 0 {
+                    self.load(2, target_mode)?
+                } else {
+                    self.pc + 3
+                };
+            }
+            Opcode::LessThan(src1_mode, src2_mode, dst_mode) => {
+                self.store(
+                    3,
+                    dst_mode,
+                    if self.load(1, src1_mode)? < self.load(2, src2_mode)? {
+                        1
                     } else {
-                        self.pc + 3
-                    };
-                }
-                Opcode::JumpIfFalse(comparand_mode, target_mode) => {
-                    self.pc = if self.load(1, comparand_mode)? == 0 {
-                        match i64::try_from(self.load(2, target_mode)?) {
-                            Ok(loc) => loc,
-                            Err(_) => return Err(IntcodeError::IndexOutOfRange),
-                        }
+                        0
+                    },
+                )?;
+                self.pc += 4;
+            }
+            Opcode::Equals(src1_mode, src2_mode, dst_mode) => {
+                self.store(
+                    3,
+                    dst_mode,
+                    if self.load(1, src1_mode)? == self.load(2, src2_mode)? {
+                        1
                     } else {
-                        self.pc + 3
-                    };
-                }
-                Opcode::LessThan(src1_mode, src2_mode, dst_mode) => {
-                    self.store(
-                        3,
-                        dst_mode,
-                        if self.load(1, src1_mode)? < self.load(2, src2_mode)? {
-                            1
-                        } else {
-                            0
-                        },
-                    )?;
-                    self.pc += 4;
-                }
-                Opcode::Equals(src1_mode, src2_mode, dst_mode) => {
-                    self.store(
-                        3,
-                        dst_mode,
-                        if self.load(1, src1_mode)? == self.load(2, src2_mode)? {
-                            1
-                        } else {
-                            0
-                        },
-                    )?;
-                    self.pc += 4;
-                }
-                Opcode::AdjustsRelativeBase(mode) => {
-                    self.relative_base += self.load(1, mode)?;
-                    self.pc += 2;
-                }
-                Opcode::Exit => return Ok(()),
+                        0
+                    },
+                )?;
+                self.pc += 4;
+            }
+            Opcode::AdjustsRelativeBase(mode) => {
+                self.relative_base += self.load(1, mode)?;
+                self.pc += 2;
             }
+            Opcode::Exit => return Ok(StepResult::Halted),
         }
+        Ok(StepResult::Continue)
     }
 }
 
-fn execute_inner<R, W>(mem: &mut [i64], input: &mut R, output: &mut W) -> Result<(), IntcodeError>
+fn execute_inner<R, W>(mem: &mut [i64], input: R, output: W) -> Result<(), IntcodeError>
 where
     R: ReadNumber,
     W: WriteNumber,
@@ -403,12 +580,15 @@ pub fn execute(
     input: &mut dyn BufRead,
     output: &mut dyn Write,
 ) -> Result<(), IntcodeError> {
-    let mut input_trait_object = BufReadNumber { buf_read: input };
-    let mut output_trait_object = WriteWriteNumber {
+    let input_trait_object = BufReadNumber { buf_read: input };
+    let output_trait_object = WriteWriteNumber {
         output,
         prompt: true,
+        radix: OutputRadix::Decimal,
+        separator: None,
+        wrote_any: false,
     };
-    execute_inner(mem, &mut input_trait_object, &mut output_trait_object)
+    execute_inner(mem, input_trait_object, output_trait_object)
 }
 
 pub fn execute_no_prompt(
@@ -416,16 +596,118 @@ pub fn execute_no_prompt(
     input: &mut dyn BufRead,
     output: &mut dyn Write,
 ) -> Result<(), IntcodeError> {
-    let mut input_trait_object = BufReadNumber { buf_read: input };
-    let mut output_trait_object = WriteWriteNumber {
+    let input_trait_object = BufReadNumber { buf_read: input };
+    let output_trait_object = WriteWriteNumber {
+        output,
+        prompt: false,
+        radix: OutputRadix::Decimal,
+        separator: None,
+        wrote_any: false,
+    };
+    execute_inner(mem, input_trait_object, output_trait_object)
+}
+
+//Parses and runs a program, reading newline separated inputs from a file or other
+//reader instead of prompting for them.
+pub fn run_with_inputs(
+    program: &str,
+    inputs: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<(), IntcodeError> {
+    let mut mem = parse_program(program)?;
+    execute_no_prompt(&mut mem, inputs, output)
+}
+
+//Runs the program in the given file. Inputs are prompted for on stdin, unless a
+//file of newline separated inputs is given.
+pub fn run_file(program_path: &str, inputs_path: Option<&str>) -> Result<(), IntcodeError> {
+    let program = std::fs::read_to_string(program_path)?;
+    if let Some(inputs_path) = inputs_path {
+        let inputs = std::fs::File::open(inputs_path)?;
+        run_with_inputs(
+            &program,
+            &mut std::io::BufReader::new(inputs),
+            &mut std::io::stdout().lock(),
+        )
+    } else {
+        let mut mem = parse_program(&program)?;
+        execute(
+            &mut mem,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout().lock(),
+        )
+    }
+}
+
+//Runs the program in the given file with newline separated inputs, returning
+//everything it outputs.
+pub fn run_file_to_string(program_path: &str, inputs: &str) -> Result<String, IntcodeError> {
+    let program = std::fs::read_to_string(program_path)?;
+    let mut output = Vec::new();
+    run_with_inputs(&program, &mut std::io::Cursor::new(inputs), &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+//Like execute_no_prompt, but formats each output in the given radix.
+pub fn execute_with_radix(
+    mem: &mut [i64],
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    radix: OutputRadix,
+) -> Result<(), IntcodeError> {
+    let input_trait_object = BufReadNumber { buf_read: input };
+    let output_trait_object = WriteWriteNumber {
         output,
         prompt: false,
+        radix,
+        separator: None,
+        wrote_any: false,
     };
-    execute_inner(mem, &mut input_trait_object, &mut output_trait_object)
+    execute_inner(mem, input_trait_object, output_trait_object)
+}
+
+//Like execute_no_prompt, but writes all the outputs on one line with the
+//separator between them, followed by a newline.
+pub fn execute_with_separator(
+    mem: &mut [i64],
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    separator: &str,
+) -> Result<(), IntcodeError> {
+    let input_trait_object = BufReadNumber { buf_read: input };
+    let output_trait_object = WriteWriteNumber {
+        output: &mut *output,
+        prompt: false,
+        radix: OutputRadix::Decimal,
+        separator: Some(separator),
+        wrote_any: false,
+    };
+    execute_inner(mem, input_trait_object, output_trait_object)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+//Connects an ASCII program's input and output to the given byte streams.
+pub fn execute_ascii(
+    mem: &mut [i64],
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), IntcodeError> {
+    let input_trait_object = ByteReadNumber { input };
+    let output_trait_object = ByteWriteNumber { output };
+    execute_inner(mem, input_trait_object, output_trait_object)
+}
+
+pub fn execute_ascii_passthrough(mem: &mut [i64]) -> Result<(), IntcodeError> {
+    execute_ascii(
+        mem,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout().lock(),
+    )
 }
 
 pub fn execute_no_io(mem: &mut [i64]) -> Result<(), IntcodeError> {
-    execute(mem, &mut std::io::empty(), &mut std::io::sink())
+    execute_inner(mem, NoIo, NoIo)
 }
 
 pub fn execute_with_channel(
@@ -433,9 +715,375 @@ pub fn execute_with_channel(
     input: &Receiver<i64>,
     output: SyncSender<i64>,
 ) -> Result<(), IntcodeError> {
-    let mut input_trait_object = ChannelReadNumber { input: &input };
-    let mut output_trait_object = ChannelWriteNumber { output };
-    execute_inner(mem, &mut input_trait_object, &mut output_trait_object)
+    let input_trait_object = ChannelReadNumber { input };
+    let output_trait_object = ChannelWriteNumber { output };
+    execute_inner(mem, input_trait_object, output_trait_object)
+}
+
+//Like execute_with_channel, but also writes each output to the log, one per line.
+pub fn execute_with_channel_and_log(
+    mem: &mut [i64],
+    input: &Receiver<i64>,
+    output: SyncSender<i64>,
+    log: &mut dyn Write,
+) -> Result<(), IntcodeError> {
+    let input_trait_object = ChannelReadNumber { input };
+    let output_trait_object = Tee {
+        first: ChannelWriteNumber { output },
+        second: WriteWriteNumber {
+            output: log,
+            prompt: false,
+            radix: OutputRadix::Decimal,
+            separator: None,
+            wrote_any: false,
+        },
+    };
+    execute_inner(mem, input_trait_object, output_trait_object)
+}
+
+#[derive(Clone)]
+struct QueueReadNumber {
+    inputs: VecDeque<i64>,
+}
+
+#[derive(Clone)]
+struct LatchWriteNumber {
+    output: Option<i64>,
+}
+
+impl ReadNumber for QueueReadNumber {
+    fn read_number(&mut self) -> Result<i64, IntcodeError> {
+        match self.inputs.pop_front() {
+            Some(num) => Ok(num),
+            None => Err(IntcodeError::EOF),
+        }
+    }
+}
+
+impl WriteNumber for LatchWriteNumber {
+    fn write_number(&mut self, num: i64) -> Result<(), IntcodeError> {
+        self.output = Some(num);
+        Ok(())
+    }
+
+    fn prompt_for_number(&mut self) -> Result<(), IntcodeError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CoroutineYield {
+    Output(i64),
+    NeedInput,
+    Halted,
+}
+
+//An intcode machine that runs on the caller's thread, pausing whenever it
+//produces an output or needs an input that has not been provided yet.
+//Cloning copies the memory, registers and any queued inputs, so a clone can be
+//run speculatively without disturbing the original.
+#[derive(Clone)]
+pub struct Coroutine {
+    state: CpuState<QueueReadNumber, LatchWriteNumber>,
+}
+
+impl Coroutine {
+    pub fn new(mem: &[i64]) -> Coroutine {
+        let input = QueueReadNumber {
+            inputs: VecDeque::new(),
+        };
+        let output = LatchWriteNumber { output: None };
+        Coroutine {
+            state: CpuState::create(mem, input, output),
+        }
+    }
+
+    pub fn resume(&mut self) -> Result<CoroutineYield, IntcodeError> {
+        loop {
+            if self.state.input.inputs.is_empty() && self.state.needs_input()? {
+                return Ok(CoroutineYield::NeedInput);
+            }
+            if let StepResult::Halted = self.state.step()? {
+                return Ok(CoroutineYield::Halted);
+            }
+            if let Some(num) = self.state.output.output.take() {
+                return Ok(CoroutineYield::Output(num));
+            }
+        }
+    }
+
+    //Returns the instruction that will run next, without running it.
+    pub fn current_instruction_text(&self) -> Result<String, IntcodeError> {
+        self.state.current_instruction_text()
+    }
+
+    pub fn resume_with_input(&mut self, input: i64) -> Result<CoroutineYield, IntcodeError> {
+        self.state.input.inputs.push_back(input);
+        self.resume()
+    }
+}
+
+//Runs the program with the given inputs until it has produced n outputs or halted,
+//whichever comes first.
+pub fn execute_until_nth_output(
+    mem: &[i64],
+    inputs: &[i64],
+    n: usize,
+) -> Result<Vec<i64>, IntcodeError> {
+    let mut co = Coroutine::new(mem);
+    let mut inputs = inputs.iter();
+    let mut next_input = None;
+    let mut outputs = Vec::new();
+    while outputs.len() < n {
+        let result = match next_input.take() {
+            Some(input) => co.resume_with_input(input)?,
+            None => co.resume()?,
+        };
+        match result {
+            CoroutineYield::Output(num) => outputs.push(num),
+            CoroutineYield::NeedInput => match inputs.next() {
+                Some(input) => next_input = Some(*input),
+                None => return Err(IntcodeError::EOF),
+            },
+            CoroutineYield::Halted => break,
+        }
+    }
+    Ok(outputs)
+}
+
+//Runs the program to completion with the given inputs. Returns the outputs and
+//any inputs the program did not read, so they can be passed along to another program.
+pub fn run_collecting_remaining(
+    mem: &[i64],
+    inputs: &[i64],
+) -> Result<(Vec<i64>, Vec<i64>), IntcodeError> {
+    let mut co = Coroutine::new(mem);
+    co.state.input.inputs.extend(inputs);
+    let mut outputs = Vec::new();
+    loop {
+        match co.resume()? {
+            CoroutineYield::Output(num) => outputs.push(num),
+            CoroutineYield::NeedInput => return Err(IntcodeError::EOF),
+            CoroutineYield::Halted => break,
+        }
+    }
+    Ok((outputs, co.state.input.inputs.into_iter().collect()))
+}
+
+//Groups a stream of outputs into fixed size messages, such as (x, y, tile) triples.
+//N must be nonzero; Chunker::<0> fails to compile.
+pub struct Chunker<const N: usize> {
+    buffer: [i64; N],
+    len: usize,
+}
+
+impl<const N: usize> Chunker<N> {
+    pub fn new() -> Chunker<N> {
+        const { assert!(N > 0, "Chunker groups must hold at least one value") };
+        Chunker {
+            buffer: [0; N],
+            len: 0,
+        }
+    }
+
+    //Returns the completed group once N values have been pushed.
+    pub fn push(&mut self, num: i64) -> Option<[i64; N]> {
+        self.buffer[self.len] = num;
+        self.len += 1;
+        if self.len == N {
+            self.len = 0;
+            Some(self.buffer)
+        } else {
+            None
+        }
+    }
+
+    //True when no partial group is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for Chunker<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//Returns (index, old value, new value) for each cell that differs. Cells past the
+//end of the shorter slice are compared against 0, matching unset intcode memory.
+pub fn memory_diff(before: &[i64], after: &[i64]) -> Vec<(usize, i64, i64)> {
+    let len = before.len().max(after.len());
+    (0..len)
+        .map(|ndx| {
+            (
+                ndx,
+                *before.get(ndx).unwrap_or(&0),
+                *after.get(ndx).unwrap_or(&0),
+            )
+        })
+        .filter(|(_, old, new)| old != new)
+        .collect()
+}
+
+//Finds jump instructions whose target is an immediate value outside of the program.
+//Returns the addresses of those jump instructions. Jumps whose target comes from
+//memory can not be checked without running the program, so they are ignored.
+pub fn check_static_jumps(mem: &[i64]) -> Vec<i64> {
+    let mut ret = Vec::new();
+    let len = mem.len() as i64;
+    let mut pc = 0;
+    while pc < len {
+        let length = match parse_instruction(mem[pc as usize]) {
+            Ok(Opcode::JumpIfTrue(_, ParameterMode::Immediate))
+            | Ok(Opcode::JumpIfFalse(_, ParameterMode::Immediate)) => {
+                if let Some(target) = mem.get(pc as usize + 2) {
+                    if *target < 0 || *target >= len {
+                        ret.push(pc);
+                    }
+                }
+                3
+            }
+            Ok(Opcode::Add(_, _, _))
+            | Ok(Opcode::Multiply(_, _, _))
+            | Ok(Opcode::LessThan(_, _, _))
+            | Ok(Opcode::Equals(_, _, _)) => 4,
+            Ok(Opcode::JumpIfTrue(_, _)) | Ok(Opcode::JumpIfFalse(_, _)) => 3,
+            Ok(Opcode::Input(_)) | Ok(Opcode::Output(_)) | Ok(Opcode::AdjustsRelativeBase(_)) => 2,
+            //Either a halt or data, so skip it.
+            Ok(Opcode::Exit) | Err(_) => 1,
+        };
+        pc += length;
+    }
+    ret
+}
+
+#[derive(Debug)]
+pub enum AssembleError {
+    UnknownMnemonic(usize, String),
+    WrongOperandCount(usize),
+    InvalidOperand(usize, String),
+    ImmediateDestination(usize),
+}
+
+impl Error for AssembleError {}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssembleError::UnknownMnemonic(line, mnemonic) => {
+                write!(f, "line {}: unknown mnemonic: {}", line, mnemonic)
+            }
+            AssembleError::WrongOperandCount(line) => {
+                write!(f, "line {}: wrong number of operands", line)
+            }
+            AssembleError::InvalidOperand(line, operand) => {
+                write!(f, "line {}: invalid operand: {}", line, operand)
+            }
+            AssembleError::ImmediateDestination(line) => {
+                write!(f, "line {}: destination operand cannot be immediate", line)
+            }
+        }
+    }
+}
+
+//(mnemonic, opcode, number of operands, index of the operand that is written to)
+const MNEMONICS: [(&str, i64, usize, Option<usize>); 10] = [
+    ("ADD", 1, 3, Some(2)),
+    ("MUL", 2, 3, Some(2)),
+    ("IN", 3, 1, Some(0)),
+    ("OUT", 4, 1, None),
+    ("JT", 5, 2, None),
+    ("JF", 6, 2, None),
+    ("LT", 7, 3, Some(2)),
+    ("EQ", 8, 3, Some(2)),
+    ("ARB", 9, 1, None),
+    ("HLT", 99, 0, None),
+];
+
+//Returns the parameter mode digit and the value for an operand.
+//  5       immediate
+//  [5]     position
+//  [rb+5]  relative
+fn parse_operand(operand: &str) -> Option<(i64, i64)> {
+    match operand.strip_prefix('[').and_then(|o| o.strip_suffix(']')) {
+        Some(inner) => match inner.trim().strip_prefix("rb") {
+            Some(offset) => {
+                let offset: String = offset.chars().filter(|c| !c.is_whitespace()).collect();
+                if offset.is_empty() {
+                    Some((2, 0))
+                } else if offset.starts_with('+') || offset.starts_with('-') {
+                    offset.parse().ok().map(|num| (2, num))
+                } else {
+                    None
+                }
+            }
+            None => inner.trim().parse().ok().map(|num| (0, num)),
+        },
+        None => operand.parse().ok().map(|num| (1, num)),
+    }
+}
+
+//Assembles one instruction per line, for example "ADD [rb+1], 5, [10]".
+//Operands are separated by commas and anything after a '#' is a comment.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssembleError> {
+    let mut ret = Vec::new();
+    for (line_ndx, line) in source.lines().enumerate() {
+        let line_num = line_ndx + 1;
+        let line = match line.find('#') {
+            Some(ndx) => &line[..ndx],
+            None => line,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = match line.find(char::is_whitespace) {
+            Some(ndx) => (&line[..ndx], line[ndx..].trim()),
+            None => (line, ""),
+        };
+        let (opcode, operand_count, dst_ndx) = match MNEMONICS
+            .iter()
+            .find(|(name, _, _, _)| name.eq_ignore_ascii_case(mnemonic))
+        {
+            Some((_, opcode, operand_count, dst_ndx)) => (*opcode, *operand_count, *dst_ndx),
+            None => {
+                return Err(AssembleError::UnknownMnemonic(
+                    line_num,
+                    mnemonic.to_string(),
+                ))
+            }
+        };
+
+        let operands: Vec<&str> = if operands.is_empty() {
+            Vec::new()
+        } else {
+            operands.split(',').map(|o| o.trim()).collect()
+        };
+        if operands.len() != operand_count {
+            return Err(AssembleError::WrongOperandCount(line_num));
+        }
+
+        let mut instruction = opcode;
+        let mut values = Vec::new();
+        let mut mode_multiplier = 100;
+        for (ndx, operand) in operands.iter().enumerate() {
+            let (mode, value) = match parse_operand(operand) {
+                Some(parsed) => parsed,
+                None => return Err(AssembleError::InvalidOperand(line_num, operand.to_string())),
+            };
+            if mode == 1 && dst_ndx == Some(ndx) {
+                return Err(AssembleError::ImmediateDestination(line_num));
+            }
+            instruction += mode * mode_multiplier;
+            mode_multiplier *= 10;
+            values.push(value);
+        }
+        ret.push(instruction);
+        ret.extend(values);
+    }
+    Ok(ret)
 }
 
 #[cfg(test)]
@@ -455,6 +1103,15 @@ mod tests {
         parse_program("turtle").expect_err("parse failed to fail");
         assert_eq!(vec![0], parse_program("0").expect("parse failed"));
         assert_eq!(vec![1, 2], parse_program("1,2").expect("parse failed"));
+        assert_eq!(
+            vec![1, 0, 0, 0, 99],
+            parse_program("1, 0, # set\n 0, 0, 99").expect("parse failed")
+        );
+        assert_eq!(
+            vec![1, 2],
+            parse_program("# header\n1,2 # trailing\n").expect("parse failed")
+        );
+        parse_program("# only a comment").expect_err("parse failed to fail");
     }
 
     fn test_a_program(input: &str, expected_output: &str) {
@@ -602,6 +1259,18 @@ mod tests {
         test_channel_io_helper(42, 1001);
     }
 
+    #[test]
+    fn test_channel_io_with_log() {
+        let mut mem = parse_program("104,1,104,-2,99").expect("failed to parse input");
+        let (_input_send, input_recv) = sync_channel(1);
+        let (output_send, output_recv) = sync_channel(2);
+        let mut log = Vec::new();
+        execute_with_channel_and_log(&mut mem, &input_recv, output_send, &mut log)
+            .expect("failed to execute");
+        assert_eq!(vec![1, -2], output_recv.iter().collect::<Vec<i64>>());
+        assert_eq!("1\n-2\n", String::from_utf8(log).unwrap());
+    }
+
     #[test]
     fn test_channel_io_closed_input() {
         let mut mem = parse_program(AROUND_EIGHT).expect("failed to parse input");
@@ -649,4 +1318,409 @@ mod tests {
             "109\n1\n204\n-1\n1001\n100\n1\n100\n1008\n100\n16\n101\n1006\n101\n0\n99\n",
         );
     }
+
+    #[test]
+    fn test_coroutine() {
+        let mem = parse_program(AROUND_EIGHT).expect("failed to parse input");
+        let mut co = Coroutine::new(&mem);
+        assert_eq!(
+            CoroutineYield::NeedInput,
+            co.resume().expect("resume failed")
+        );
+        assert_eq!(
+            CoroutineYield::Output(1000),
+            co.resume_with_input(8).expect("resume failed")
+        );
+        assert_eq!(CoroutineYield::Halted, co.resume().expect("resume failed"));
+        assert_eq!(CoroutineYield::Halted, co.resume().expect("resume failed"));
+    }
+
+    #[test]
+    fn test_coroutine_chain() {
+        //Reads a number and outputs double it, forever.
+        let mem = parse_program("3,9,1002,9,2,9,4,9,1105,1,0").expect("failed to parse input");
+        let mut first = Coroutine::new(&mem);
+        let mut second = Coroutine::new(&mem);
+        for input in 1..4 {
+            let middle = match first.resume_with_input(input).expect("resume failed") {
+                CoroutineYield::Output(num) => num,
+                other => panic!("unexpected yield: {:?}", other),
+            };
+            assert_eq!(
+                CoroutineYield::Output(input * 4),
+                second.resume_with_input(middle).expect("resume failed")
+            );
+        }
+        assert_eq!(
+            CoroutineYield::NeedInput,
+            first.resume().expect("resume failed")
+        );
+        assert_eq!(
+            CoroutineYield::NeedInput,
+            second.resume().expect("resume failed")
+        );
+    }
+
+    #[test]
+    fn test_execute_until_nth_output() {
+        //Counts up from the input forever.
+        let mem =
+            parse_program("3,11,4,11,1001,11,1,11,1105,1,2,0").expect("failed to parse input");
+        assert_eq!(
+            vec![5, 6],
+            execute_until_nth_output(&mem, &[5], 2).expect("execute failed")
+        );
+        assert_eq!(
+            Vec::<i64>::new(),
+            execute_until_nth_output(&mem, &[5], 0).expect("execute failed")
+        );
+        match execute_until_nth_output(&mem, &[], 2) {
+            Err(IntcodeError::EOF) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        //Halts after a single output.
+        let mem = parse_program("104,7,99").expect("failed to parse input");
+        assert_eq!(
+            vec![7],
+            execute_until_nth_output(&mem, &[], 2).expect("execute failed")
+        );
+    }
+
+    fn create_test_cpu(
+        mem: &[i64],
+        pc: i64,
+        relative_base: i64,
+    ) -> CpuState<QueueReadNumber, LatchWriteNumber> {
+        CpuState::create_with_registers(
+            mem,
+            pc,
+            relative_base,
+            QueueReadNumber {
+                inputs: VecDeque::new(),
+            },
+            LatchWriteNumber { output: None },
+        )
+    }
+
+    #[test]
+    fn test_relative_add_with_preset_registers() {
+        //The add at index 4 reads indices 11 and 12 and writes index 13.
+        let mem = [99, 99, 99, 99, 22201, 1, 2, 3, 99, 0, 0, 4, 5, 0];
+        let mut cpu = create_test_cpu(&mem, 4, 10);
+        match cpu.step().expect("step failed") {
+            StepResult::Continue => {}
+            StepResult::Halted => panic!("unexpected halt"),
+        }
+        assert_eq!(8, cpu.pc);
+        assert_eq!(10, cpu.relative_base);
+        assert_eq!(9, cpu.load_raw(13).expect("load failed"));
+        match cpu.step().expect("step failed") {
+            StepResult::Halted => {}
+            StepResult::Continue => panic!("expected halt"),
+        }
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            # doubles the input
+            IN [9]
+            MUL [9], 2, [9]
+            out [9]
+            HLT
+        ";
+        let mem = assemble(source).expect("failed to assemble");
+        assert_eq!(vec![3, 9, 1002, 9, 2, 9, 4, 9, 99], mem);
+        assert_eq!(
+            vec![42],
+            execute_until_nth_output(&mem, &[21], 1).expect("execute failed")
+        );
+
+        assert_eq!(
+            vec![109, 5, 21101, -3, 4, 1],
+            assemble("ARB 5\nADD -3, 4, [rb + 1]").expect("failed to assemble")
+        );
+        assert_eq!(
+            vec![1206, -2, 7],
+            assemble("JF [rb-2], 7").expect("failed to assemble")
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        match assemble("NOP") {
+            Err(AssembleError::UnknownMnemonic(1, _)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match assemble("HLT\nOUT") {
+            Err(AssembleError::WrongOperandCount(2)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match assemble("OUT [x]") {
+            Err(AssembleError::InvalidOperand(1, _)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match assemble("ADD 1, 2, 3") {
+            Err(AssembleError::ImmediateDestination(1)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn test_radix_program(program: &str, radix: OutputRadix, expected_output: &str) {
+        let mut mem = parse_program(program).expect("failed to parse input");
+        let mut output = Vec::new();
+        execute_with_radix(&mut mem, &mut std::io::empty(), &mut output, radix)
+            .expect("execute failed");
+        assert_eq!(expected_output, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_output_separator() {
+        let mut mem = parse_program("104,1,104,2,104,3,99").expect("failed to parse input");
+        let mut output = Vec::new();
+        execute_with_separator(&mut mem, &mut std::io::empty(), &mut output, ",")
+            .expect("execute failed");
+        assert_eq!("1,2,3\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_output_radix() {
+        test_radix_program("104,255,104,-10,99", OutputRadix::Hexadecimal, "ff\n-a\n");
+        test_radix_program("104,5,104,-2,99", OutputRadix::Binary, "101\n-10\n");
+        test_radix_program("104,255,99", OutputRadix::Decimal, "255\n");
+    }
+
+    #[test]
+    fn test_run_collecting_remaining() {
+        let mem = parse_program("3,0,4,0,99").expect("failed to parse input");
+        assert_eq!(
+            (vec![1], vec![2, 3]),
+            run_collecting_remaining(&mem, &[1, 2, 3]).expect("execute failed")
+        );
+        match run_collecting_remaining(&mem, &[]) {
+            Err(IntcodeError::EOF) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_no_io() {
+        let mut mem = parse_program("104,1,3,0,99").expect("failed to parse input");
+        match execute_no_io(&mut mem) {
+            Err(IntcodeError::NoInputAvailable) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_run_with_inputs() {
+        let mut output = Vec::new();
+        run_with_inputs(
+            "3,0,3,1,1,0,1,0,4,0,99",
+            &mut std::io::Cursor::new("40\n2\n"),
+            &mut output,
+        )
+        .expect("execute failed");
+        assert_eq!("42\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_memory_diff() {
+        let before = parse_program("1,0,0,0,99").expect("failed to parse input");
+        let mut after = before.clone();
+        execute_no_io(&mut after).expect("execute failed");
+        assert_eq!(vec![(0, 1, 2)], memory_diff(&before, &after));
+        assert_eq!(vec![(1, 0, 5), (2, 3, 0)], memory_diff(&[1, 0, 3], &[1, 5]));
+        assert!(memory_diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_check_static_jumps() {
+        assert!(check_static_jumps(&parse_program(AROUND_EIGHT).unwrap()).is_empty());
+        //The jump at 2 targets 100, the one at 5 targets 0.
+        let mem = parse_program("3,0,1105,0,100,1006,0,0,99").unwrap();
+        assert_eq!(vec![2], check_static_jumps(&mem));
+        //Targets read from memory are not checked.
+        let mem = parse_program("3,0,1005,0,100,6,0,-1,99").unwrap();
+        assert_eq!(vec![2], check_static_jumps(&mem));
+        let mem = parse_program("1106,0,-1").unwrap();
+        assert_eq!(vec![0], check_static_jumps(&mem));
+    }
+
+    #[test]
+    fn test_current_instruction_text() {
+        let mem = parse_program("109,-2,21101,3,4,5,1006,7,0,99").unwrap();
+        let mut cpu = create_test_cpu(&mem, 0, 0);
+        let expected = ["ARB -2", "ADD 3, 4, [rb+5]", "JF [7], 0", "HLT"];
+        for text in expected.iter() {
+            assert_eq!(
+                *text,
+                cpu.current_instruction_text().expect("decode failed")
+            );
+            assert_eq!(
+                *text,
+                cpu.current_instruction_text().expect("decode failed")
+            );
+            cpu.step().expect("step failed");
+        }
+        assert_eq!(
+            mem,
+            assemble(&expected.join("\n")).expect("failed to assemble")
+        );
+
+        let cpu = create_test_cpu(&[22201, 1, -1, 0], 0, 0);
+        assert_eq!(
+            "ADD [rb+1], [rb-1], [rb+0]",
+            cpu.current_instruction_text().expect("decode failed")
+        );
+        let cpu = create_test_cpu(&[42], 0, 0);
+        cpu.current_instruction_text()
+            .expect_err("decoding should fail");
+    }
+
+    fn test_ascii_program(program: &str, input: &str, expected_output: &[u8]) {
+        let mut mem = parse_program(program).expect("failed to parse input");
+        let mut output = Vec::new();
+        execute_ascii(&mut mem, &mut input.as_bytes(), &mut output).expect("execute failed");
+        assert_eq!(expected_output, &output[..]);
+    }
+
+    #[test]
+    fn test_ascii() {
+        test_ascii_program("104,72,104,105,99", "", b"Hi");
+        test_ascii_program("3,0,4,0,3,0,4,0,99", "ok", b"ok");
+        test_ascii_program("104,10,104,1000,99", "", b"\n1000\n");
+    }
+
+    //Tests below execute one instruction from a preset state and check the
+    //registers and any memory written.
+    fn step_once(
+        mem: &[i64],
+        pc: i64,
+        relative_base: i64,
+        inputs: &[i64],
+    ) -> CpuState<QueueReadNumber, LatchWriteNumber> {
+        let mut cpu = create_test_cpu(mem, pc, relative_base);
+        cpu.input.inputs.extend(inputs);
+        match cpu.step().expect("step failed") {
+            StepResult::Continue => {}
+            StepResult::Halted => panic!("unexpected halt"),
+        }
+        cpu
+    }
+
+    fn assert_step(
+        cpu: &CpuState<QueueReadNumber, LatchWriteNumber>,
+        pc: i64,
+        relative_base: i64,
+        cell: i64,
+        value: i64,
+    ) {
+        assert_eq!(pc, cpu.pc);
+        assert_eq!(relative_base, cpu.relative_base);
+        assert_eq!(value, cpu.load_raw(cell).expect("load failed"));
+    }
+
+    #[test]
+    fn test_step_add() {
+        let cpu = step_once(&[1, 5, 6, 7, 99, 3, 4, 0], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 7, 7);
+        let cpu = step_once(&[1101, 3, 4, 5, 99, 0], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 7);
+        let cpu = step_once(&[22201, 0, 1, 2, 99, 3, 4, 0], 0, 5, &[]);
+        assert_step(&cpu, 4, 5, 7, 7);
+    }
+
+    #[test]
+    fn test_step_mul() {
+        let cpu = step_once(&[2, 5, 6, 7, 99, 3, 4, 0], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 7, 12);
+        let cpu = step_once(&[1102, 3, 4, 5, 99, 0], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 12);
+        let cpu = step_once(&[22202, 0, 1, 2, 99, 3, 4, 0], 0, 5, &[]);
+        assert_step(&cpu, 4, 5, 7, 12);
+    }
+
+    #[test]
+    fn test_step_input_relative() {
+        let cpu = step_once(&[203, -1, 99, 0], 0, 4, &[55]);
+        assert_step(&cpu, 2, 4, 3, 55);
+    }
+
+    #[test]
+    fn test_step_output_immediate() {
+        let cpu = step_once(&[104, 42, 99], 0, 0, &[]);
+        assert_eq!(2, cpu.pc);
+        assert_eq!(Some(42), cpu.output.output);
+    }
+
+    #[test]
+    fn test_step_jumps() {
+        assert_eq!(7, step_once(&[1105, 1, 7], 0, 0, &[]).pc);
+        assert_eq!(3, step_once(&[1105, 0, 7], 0, 0, &[]).pc);
+        assert_eq!(7, step_once(&[1106, 0, 7], 0, 0, &[]).pc);
+        assert_eq!(3, step_once(&[1106, 1, 7], 0, 0, &[]).pc);
+    }
+
+    #[test]
+    fn test_step_compare() {
+        let cpu = step_once(&[1107, 1, 2, 5, 99, -1], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 1);
+        let cpu = step_once(&[1107, 2, 1, 5, 99, -1], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 0);
+        let cpu = step_once(&[1108, 3, 3, 5, 99, -1], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 1);
+        let cpu = step_once(&[1108, 3, 4, 5, 99, -1], 0, 0, &[]);
+        assert_step(&cpu, 4, 0, 5, 0);
+    }
+
+    #[test]
+    fn test_step_adjust_relative_base() {
+        let cpu = step_once(&[109, -3, 99], 0, 10, &[]);
+        assert_step(&cpu, 2, 7, 0, 109);
+        //Relative mode reads index 0 + 3.
+        let cpu = step_once(&[209, 3, 99, 4], 0, 0, &[]);
+        assert_step(&cpu, 2, 4, 0, 209);
+    }
+
+    #[test]
+    fn test_chunker() {
+        let mut coroutine =
+            Coroutine::new(&parse_program("104,1,104,2,104,3,104,4,104,5,104,6,104,7,99").unwrap());
+        let mut chunker = Chunker::<3>::new();
+        let mut triples = Vec::new();
+        while let CoroutineYield::Output(num) = coroutine.resume().expect("resume failed") {
+            if let Some(triple) = chunker.push(num) {
+                triples.push(triple);
+            }
+        }
+        assert_eq!(vec![[1, 2, 3], [4, 5, 6]], triples);
+        assert!(!chunker.is_empty());
+        assert_eq!(None, chunker.push(8));
+        assert_eq!(Some([7, 8, 9]), chunker.push(9));
+        assert!(chunker.is_empty());
+    }
+
+    #[test]
+    fn test_coroutine_clone() {
+        let mem = parse_program(AROUND_EIGHT).expect("failed to parse input");
+        let mut original = Coroutine::new(&mem);
+        assert_eq!(CoroutineYield::NeedInput, original.resume().unwrap());
+
+        //Each clone picks up from the paused state independently.
+        for (input, expected) in [(7, 999), (8, 1000), (9, 1001)].iter() {
+            let mut fork = original.clone();
+            assert_eq!(
+                CoroutineYield::Output(*expected),
+                fork.resume_with_input(*input).unwrap()
+            );
+            assert_eq!(CoroutineYield::Halted, fork.resume().unwrap());
+        }
+
+        assert_eq!(
+            CoroutineYield::Output(1000),
+            original.resume_with_input(8).unwrap()
+        );
+    }
 }
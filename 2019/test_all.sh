@@ -2,10 +2,15 @@
 
 set -e
 
+# Extra arguments are passed to the test binaries, for example
+# `./test_all.sh --include-ignored` also checks the answers for the real inputs.
+# Those tests are ignored by default because they run against the input.txt checked
+# in next to each crate, whose answers are specific to one puzzle account. Run them
+# for a single crate with `cargo test -- --ignored`.
 for f in $(find . -type f -name Cargo.toml -print)
 do
 	echo $f
-	cargo test --manifest-path=$f
+	cargo test --manifest-path=$f -- "$@"
 done
 
 echo SUCCESS!